@@ -1,15 +1,42 @@
 use crate::{ActorId, UserId};
 use serde::{Serialize, de::DeserializeOwned};
-use std::{collections::HashMap, fmt::Debug};
+use std::{collections::HashMap, fmt::Debug, time::Duration};
 use ts_rs::TS;
 
 pub trait Action:
     Clone + Debug + Serialize + DeserializeOwned + TS + Send + Sync + 'static
 {
-    type Shared: Default;
-    type User: Default;
-    type Msg: Serialize + DeserializeOwned + TS + Send + Sync + 'static;
+    type Shared: Clone + Default + Serialize + DeserializeOwned + Send;
+    type User: Clone + Default + Serialize + DeserializeOwned + Send;
+    type Msg: Clone + Serialize + DeserializeOwned + TS + Send + Sync + 'static;
+    type Summary: Serialize + Send + 'static;
+    type Rules: Clone + Debug + Send + 'static;
+    /// How an open lobby is described to players looking for a game.
+    type Listing: Clone + Debug + PartialEq + Serialize + Send + Sync + 'static;
+    /// What a player asks of a quick match, such as the table size.
+    type Preference: Clone
+        + Debug
+        + PartialEq
+        + Serialize
+        + DeserializeOwned
+        + TS
+        + Send
+        + Sync
+        + 'static;
     fn can_join(shared: &Self::Shared, user: &HashMap<UserId, Self::User>) -> bool;
+    fn listing(shared: &Self::Shared, user: &HashMap<UserId, Self::User>) -> Self::Listing;
+    /// Rules for a quick-match lobby, from the server's rules and what the
+    /// players asked for, and how many players it waits for. `None` turns
+    /// the request away.
+    fn match_rules(
+        rules: &Self::Rules,
+        preference: &Self::Preference,
+    ) -> Option<(Self::Rules, usize)>;
+    /// Sent to everyone waiting for a quick match whenever their queue
+    /// changes: their place in it, how many wait and how many are needed.
+    fn queued_msg(position: usize, waiting: usize, players: usize) -> Self::Msg;
+    /// Shared state for a freshly created actor.
+    fn shared(rules: &Self::Rules) -> Self::Shared;
     fn update(
         self,
         shared: &mut Self::Shared,
@@ -17,5 +44,46 @@ pub trait Action:
         user_id: UserId,
     );
     fn join_msg(actor_id: ActorId) -> Self::Msg;
+    /// Sent to every connected user when the server is going down.
+    fn shutdown_msg(reconnect_after: Duration) -> Self::Msg;
+    /// Sent before closing a socket whose client speaks an incompatible
+    /// protocol, with the server's revision and the oldest it accepts.
+    fn incompatible_msg(version: u16, min_version: u16) -> Self::Msg;
+    /// Rewrites `msg` for a peer on protocol `version`, older than this
+    /// build, into a shape that peer can decode.
+    fn downgrade(msg: Self::Msg, version: u16) -> Self::Msg;
     fn msg(shared: &Self::Shared, user: &HashMap<UserId, Self::User>) -> Vec<(UserId, Self::Msg)>;
+    /// Called when a user takes a seat.
+    fn joined(shared: &mut Self::Shared, user_id: UserId);
+    /// Called when a seated user's first connection opens and when their
+    /// last one closes. A restored table starts with everyone disconnected.
+    fn connected(
+        shared: &mut Self::Shared,
+        user: &mut HashMap<UserId, Self::User>,
+        user_id: UserId,
+        connected: bool,
+    );
+    /// Actions bot players want to take now. The actor feeds them back through
+    /// its own action channel, exactly like a human's.
+    fn bots(shared: &Self::Shared, user: &HashMap<UserId, Self::User>) -> Vec<(UserId, Self)>;
+    /// Records a user's connection round-trip time, or `None` once they
+    /// disconnect, so the table can show who is lagging.
+    fn latency(shared: &mut Self::Shared, user_id: UserId, rtt: Option<Duration>);
+    /// Removes a user, keeping the game playable for everyone else.
+    fn leave(shared: &mut Self::Shared, user: &mut HashMap<UserId, Self::User>, user_id: UserId);
+    /// Ends the game immediately. Returns false if there was no game to end.
+    fn end(shared: &mut Self::Shared) -> bool;
+    /// Suggested moves for `user_id`, using only what they can see.
+    fn hint(
+        shared: &Self::Shared,
+        user: &HashMap<UserId, Self::User>,
+        user_id: UserId,
+    ) -> Option<Self::Msg>;
+    /// Short label for the current phase, used in metrics.
+    fn status(shared: &Self::Shared) -> &'static str;
+    /// The current game's number at its table and the seed it was dealt
+    /// from, once there is one. A new number marks a new game to record.
+    fn dealt(shared: &Self::Shared) -> Option<(u32, u64)>;
+    /// Returns the result of the game once it has finished.
+    fn summary(shared: &Self::Shared, user: &HashMap<UserId, Self::User>) -> Option<Self::Summary>;
 }
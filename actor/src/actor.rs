@@ -1,89 +1,265 @@
-use crate::Action;
+use crate::{
+    Action, ActorConfig, ActorDump, ActorInfo, ActorList, AdminReq, Delivery, MigrateError, Outbox,
+    PlayerInfo, Record, SessionPolicy, SnapshotDir,
+    matchmaking::{Forming, Matchmaker},
+    telemetry,
+};
 use arrayvec::ArrayString;
 use kanal::{Receiver, Sender};
+use metrics::{counter, gauge, histogram};
 use std::{
     collections::HashMap,
-    sync::Arc,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
     thread,
     time::{Duration, Instant},
 };
-use tokio::sync::RwLock;
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
 pub type UserId = ArrayString<32>;
 pub type ActorId = Uuid;
 
-const RECV_BOUND: usize = 128;
-const TICK_MS: Duration = Duration::from_millis(10);
-
-#[derive(Clone, Debug, Default)]
-pub struct ActorList(Arc<RwLock<Vec<(ActorId, u8)>>>);
-
-impl ActorList {
-    pub async fn read(&self) -> Vec<(ActorId, u8)> {
-        self.0.read().await.clone()
-    }
-    pub fn write(&self, list: Vec<(ActorId, u8)>) {
-        let mut lock = self.0.blocking_write();
-        *lock = list;
-    }
-}
-
-pub fn actor_loop<A: Action>(recv: Receiver<JoinReq<A>>, actor_list: ActorList) {
-    info!(
-        "Actor loop started with {}ms tick rate",
-        TICK_MS.as_millis()
-    );
-    let mut actors = ActorSystem::<A>::new(recv, actor_list);
-    let mut next = Instant::now() + TICK_MS;
-    loop {
+pub fn actor_loop<A: Action>(
+    recv: Receiver<JoinReq<A>>,
+    actor_list: ActorList<A::Listing>,
+    record: Sender<Record<A>>,
+    snapshots: SnapshotDir,
+    config: ActorConfig,
+    rules: A::Rules,
+) {
+    let tick = config.tick();
+    info!("Actor loop started with {}ms tick rate", tick.as_millis());
+    let mut actors = ActorSystem::<A>::new(recv, actor_list, record, snapshots, config, rules);
+    actors.restore();
+    let mut next = Instant::now() + tick;
+    while actors.running {
+        let start = Instant::now();
         actors.update();
         let now = Instant::now();
+        histogram!(telemetry::TICK_SECONDS).record(now - start);
         if now < next {
             thread::sleep(next - now);
         }
-        next += TICK_MS;
+        next += tick;
     }
+    info!("Actor loop stopped");
 }
 
 pub struct ActorSystem<A: Action> {
     recv: Receiver<JoinReq<A>>,
     actors: HashMap<ActorId, Actor<A>>,
     users: HashMap<UserId, UserHandle<A>>,
-    actor_list: ActorList,
+    actor_list: ActorList<A::Listing>,
+    record: Sender<Record<A>>,
+    snapshots: SnapshotDir,
+    last_snapshot: Instant,
+    running: bool,
+    config: ActorConfig,
+    rules: A::Rules,
+    phases: HashMap<&'static str, usize>,
+    matchmaker: Matchmaker<A>,
 }
 
 impl<A: Action> ActorSystem<A> {
-    pub fn new(recv: Receiver<JoinReq<A>>, actor_list: ActorList) -> Self {
+    pub fn new(
+        recv: Receiver<JoinReq<A>>,
+        actor_list: ActorList<A::Listing>,
+        record: Sender<Record<A>>,
+        snapshots: SnapshotDir,
+        config: ActorConfig,
+        rules: A::Rules,
+    ) -> Self {
         Self {
             recv,
             actors: Default::default(),
             users: Default::default(),
             actor_list,
+            record,
+            snapshots,
+            last_snapshot: Instant::now(),
+            running: true,
+            config,
+            rules,
+            phases: HashMap::new(),
+            matchmaker: Matchmaker::default(),
+        }
+    }
+    /// Rebuilds actors from the snapshot directory. Restored users start out
+    /// disconnected and rejoin through the usual reconnect path.
+    pub fn restore(&mut self) {
+        for (actor_id, shared, user) in self.snapshots.load::<A>() {
+            let (actor, send_action) = Actor::restore(shared, user, self.config.recv_bound);
+            actor.user.keys().for_each(|user_id| {
+                self.users.insert(
+                    *user_id,
+                    UserHandle {
+                        connected: false,
+                        actor_id,
+                        send_action: send_action.clone(),
+                    },
+                );
+            });
+            info!(%actor_id, player_count = actor.user.len(), "Restored actor from snapshot");
+            self.actors.insert(actor_id, actor);
+        }
+        self.update_list();
+    }
+    fn snapshot(&mut self) {
+        self.actors
+            .iter_mut()
+            .filter(|(_, actor)| actor.dirty)
+            .for_each(|(actor_id, actor)| {
+                match self
+                    .snapshots
+                    .save::<A>(*actor_id, &actor.shared, &actor.user)
+                {
+                    Ok(()) => actor.dirty = false,
+                    Err(err) => error!(%actor_id, error = %err, "Failed to write snapshot"),
+                }
+            });
+        self.last_snapshot = Instant::now();
+    }
+    /// Tells every connected user to come back later, writes final snapshots
+    /// and stops the actor loop. Joins still queued are dropped unanswered.
+    fn shutdown(&mut self, reconnect_after: Duration) {
+        let mut notified = 0;
+        self.actors.values().for_each(|actor| {
+            actor.sessions.keys().for_each(|user_id| {
+                if self.users.get(user_id).is_some_and(|h| h.connected) {
+                    let msg = A::shutdown_msg(reconnect_after);
+                    actor.fan_out(user_id, msg, |send, msg| match send.push(msg) {
+                        Delivery::Queued => notified += 1,
+                        delivery => {
+                            warn!(%user_id, ?delivery, "Failed to send shutdown message")
+                        }
+                    });
+                }
+            });
+        });
+        self.matchmaker.sessions().for_each(|(user_id, session)| {
+            match session.send.push(A::shutdown_msg(reconnect_after)) {
+                Delivery::Queued => notified += 1,
+                delivery => warn!(%user_id, ?delivery, "Failed to send shutdown message"),
+            }
+        });
+        self.snapshot();
+        self.actor_list.close();
+        self.running = false;
+        info!(
+            notified,
+            actor_count = self.actors.len(),
+            "Actor system shutting down"
+        );
+    }
+    fn remove_actor(&mut self, actor_id: ActorId) {
+        if let Some(actor) = self.actors.remove(&actor_id) {
+            actor.user.keys().for_each(|id| {
+                self.users.remove(id);
+            });
+            self.snapshots.remove(actor_id);
+            self.update_list();
+            send_record(&self.record, Record::Closed { actor_id });
         }
     }
     fn update(&mut self) {
-        self.actors.values_mut().for_each(|actor| {
-            actor.update();
+        let record = &self.record;
+        let bot_delay = self.config.bot_delay();
+        let mut list_changed = false;
+        self.actors.iter_mut().for_each(|(actor_id, actor)| {
+            let open = actor.open();
+            actor.update(*actor_id, record);
+            actor.bots(*actor_id, bot_delay);
+            list_changed |= open != actor.open();
+            // Each tick's messages are the whole table, so a connection that
+            // has not sent the last one only needs this one.
             let msgs = A::msg(&actor.shared, &actor.user);
             msgs.into_iter().for_each(|(user_id, msg)| {
-                if let Some(send) = actor.server_msgs.get(&user_id)
-                    && let Err(err) = send.send(msg)
-                {
-                    warn!(%user_id, error = %err, "Failed to send server message to user");
-                }
+                actor.fan_out(&user_id, msg, |send, msg| match send.replace(msg) {
+                    Delivery::Queued => counter!(telemetry::SERVER_MSGS).increment(1),
+                    Delivery::Coalesced => {
+                        counter!(telemetry::SERVER_MSGS).increment(1);
+                        counter!(telemetry::SERVER_MSG_COALESCED).increment(1);
+                    }
+                    delivery => {
+                        counter!(telemetry::SERVER_MSG_FAILURES).increment(1);
+                        warn!(%user_id, ?delivery, "Failed to send server message to user");
+                    }
+                });
             });
+            match A::summary(&actor.shared, &actor.user) {
+                Some(summary) if !actor.finished => {
+                    actor.finished = true;
+                    let players = actor.user.keys().copied().collect();
+                    send_record(
+                        record,
+                        Record::Finished {
+                            actor_id: *actor_id,
+                            game: actor.dealt.unwrap_or_default(),
+                            players,
+                            summary,
+                        },
+                    );
+                }
+                Some(_) => (),
+                None => actor.finished = false,
+            }
         });
-        while let Ok(Some(join)) = self.recv.try_recv() {
+        if list_changed {
+            self.update_list();
+        }
+        while self.running
+            && let Ok(Some(join)) = self.recv.try_recv()
+        {
             self.join(join);
         }
+        if self.last_snapshot.elapsed() >= self.config.snapshot_interval() {
+            let timeout = self.config.restore_timeout();
+            let expired: Vec<_> = self
+                .actors
+                .iter()
+                .filter(|(_, a)| a.idle_since.is_some_and(|t| t.elapsed() >= timeout))
+                .map(|(id, _)| *id)
+                .collect();
+            expired.into_iter().for_each(|actor_id| {
+                self.remove_actor(actor_id);
+                info!(%actor_id, "Removed restored actor - no users reconnected");
+            });
+            self.snapshot();
+        }
+        self.gauges();
+    }
+    fn gauges(&mut self) {
+        gauge!(telemetry::ACTORS).set(self.actors.len() as f64);
+        gauge!(telemetry::QUEUED).set(self.matchmaker.waiting() as f64);
+        let connected = self.users.values().filter(|h| h.connected).count();
+        gauge!(telemetry::USERS, "state" => "connected").set(connected as f64);
+        gauge!(telemetry::USERS, "state" => "disconnected")
+            .set((self.users.len() - connected) as f64);
+        let sessions: usize = self
+            .actors
+            .values()
+            .flat_map(|actor| actor.sessions.values())
+            .map(Vec::len)
+            .sum();
+        gauge!(telemetry::SESSIONS).set(sessions as f64);
+        let mut phases: HashMap<&'static str, usize> =
+            self.phases.keys().map(|phase| (*phase, 0)).collect();
+        self.actors.values().for_each(|actor| {
+            *phases.entry(A::status(&actor.shared)).or_default() += 1;
+        });
+        phases.iter().for_each(|(phase, count)| {
+            gauge!(telemetry::GAMES, "phase" => *phase).set(*count as f64);
+        });
+        self.phases = phases;
     }
     fn update_list(&mut self) {
         let actor_list = self
             .actors
             .iter()
-            .filter_map(|(id, a)| a.open().map(|i| (*id, i)))
+            .filter_map(|(id, a)| a.open().map(|lobby| (*id, lobby)))
             .collect();
         self.actor_list.write(actor_list);
     }
@@ -94,7 +270,14 @@ impl<A: Action> ActorSystem<A> {
                 actor_id,
                 send_server_msg,
                 send_sender,
+                active,
             } => {
+                let session = Session {
+                    send: send_server_msg,
+                    active,
+                };
+                // Joining a lobby gives up a place in the quick-match queue.
+                self.matchmaker.remove(&user_id);
                 if let Some(UserHandle {
                     connected,
                     actor_id,
@@ -103,35 +286,39 @@ impl<A: Action> ActorSystem<A> {
                     && let Some(actor) = self.actors.get_mut(actor_id)
                 {
                     match send_sender.send(send_action.clone()) {
-                        Ok(()) => {
-                            if let Some(send) = actor.server_msgs.get_mut(&user_id) {
-                                match send_server_msg.send(A::join_msg(*actor_id)) {
-                                    Ok(()) => {
-                                        *send = send_server_msg;
-                                        *connected = true;
-                                        info!(%user_id, %actor_id, "User reconnected to actor");
-                                    }
-                                    Err(err) => {
-                                        error!(%user_id, %actor_id, error = %err, "Failed to send join message on reconnect");
-                                    }
+                        Ok(()) => match session.send.push(A::join_msg(*actor_id)) {
+                            Delivery::Queued => {
+                                let sessions = actor.add_session(user_id, session, &self.config);
+                                actor.idle_since = None;
+                                if !*connected {
+                                    A::connected(&mut actor.shared, &mut actor.user, user_id, true);
                                 }
+                                *connected = true;
+                                send_record(
+                                    &self.record,
+                                    Record::Join {
+                                        user_id,
+                                        actor_id: *actor_id,
+                                    },
+                                );
+                                info!(%user_id, %actor_id, sessions, "User reconnected to actor");
                             }
-                        }
+                            delivery => {
+                                error!(%user_id, %actor_id, ?delivery, "Failed to send join message on reconnect");
+                            }
+                        },
                         Err(err) => {
                             error!(%user_id, error = %err, "Failed to send action sender on reconnect");
                         }
                     }
                 } else if let Some(actor) = actor_id.and_then(|id| self.actors.get_mut(&id))
                     && <A as Action>::can_join(&actor.shared, &actor.user)
-                    && let Some(send_action) = actor
-                        .user
-                        .keys()
-                        .find_map(|id| self.users.get(id).map(|h| h.send_action.clone()))
                 {
                     let aid = actor_id.unwrap();
+                    let send_action = actor.send_action.clone();
                     match send_sender.send(send_action.clone()) {
-                        Ok(()) => match send_server_msg.send(A::join_msg(aid)) {
-                            Ok(()) => {
+                        Ok(()) => match session.send.push(A::join_msg(aid)) {
+                            Delivery::Queued => {
                                 self.users.insert(
                                     user_id,
                                     UserHandle {
@@ -141,11 +328,23 @@ impl<A: Action> ActorSystem<A> {
                                     },
                                 );
                                 actor.user.insert(user_id, Default::default());
-                                actor.server_msgs.insert(user_id, send_server_msg);
-                                info!(%user_id, actor_id = %aid, player_count = actor.user.len(), "User joined existing actor");
+                                A::joined(&mut actor.shared, user_id);
+                                A::connected(&mut actor.shared, &mut actor.user, user_id, true);
+                                actor.add_session(user_id, session, &self.config);
+                                actor.dirty = true;
+                                let player_count = actor.user.len();
+                                self.update_list();
+                                send_record(
+                                    &self.record,
+                                    Record::Join {
+                                        user_id,
+                                        actor_id: aid,
+                                    },
+                                );
+                                info!(%user_id, actor_id = %aid, player_count, "User joined existing actor");
                             }
-                            Err(err) => {
-                                error!(%user_id, actor_id = %aid, error = %err, "Failed to send join message");
+                            delivery => {
+                                error!(%user_id, actor_id = %aid, ?delivery, "Failed to send join message");
                             }
                         },
                         Err(err) => {
@@ -153,17 +352,17 @@ impl<A: Action> ActorSystem<A> {
                         }
                     }
                 } else {
-                    let (actor, send_action) = Actor::spawn(user_id, send_server_msg);
+                    let (actor, send_action) = Actor::spawn(
+                        user_id,
+                        session,
+                        A::shared(&self.rules),
+                        self.config.recv_bound,
+                    );
                     match send_sender.send(send_action.clone()) {
                         Ok(()) => {
                             let actor_id = Uuid::now_v7();
-                            match actor
-                                .server_msgs
-                                .get(&user_id)
-                                .unwrap()
-                                .send(A::join_msg(actor_id))
-                            {
-                                Ok(()) => {
+                            match actor.sessions[&user_id][0].send.push(A::join_msg(actor_id)) {
+                                Delivery::Queued => {
                                     self.actors.insert(actor_id, actor);
                                     self.users.insert(
                                         user_id,
@@ -174,10 +373,11 @@ impl<A: Action> ActorSystem<A> {
                                         },
                                     );
                                     self.update_list();
+                                    send_record(&self.record, Record::Join { user_id, actor_id });
                                     info!(%user_id, %actor_id, "Created new actor for user");
                                 }
-                                Err(err) => {
-                                    error!(%user_id, %actor_id, error = %err, "Failed to send join message for new actor");
+                                delivery => {
+                                    error!(%user_id, %actor_id, ?delivery, "Failed to send join message for new actor");
                                 }
                             }
                         }
@@ -187,34 +387,286 @@ impl<A: Action> ActorSystem<A> {
                     }
                 }
             }
+            JoinReq::QuickMatch {
+                user_id,
+                preference,
+                send_server_msg,
+                send_sender,
+                active,
+            } => self.quick_match(user_id, preference, send_server_msg, send_sender, active),
             JoinReq::Disconnect { user_id } => {
-                let actor_id = if let Some(handle) = self.users.get_mut(&user_id) {
-                    handle.connected = false;
-                    debug!(%user_id, actor_id = %handle.actor_id, "User disconnected");
-                    Some(handle.actor_id)
-                } else {
-                    debug!(%user_id, "Disconnect for unknown user");
-                    None
+                let Some(handle) = self.users.get_mut(&user_id) else {
+                    if self.matchmaker.disconnect(&user_id) {
+                        debug!(%user_id, "User left the quick-match queue");
+                    } else {
+                        debug!(%user_id, "Disconnect for unknown user");
+                    }
+                    return;
                 };
-                if let Some(users) = actor_id.and_then(|id| {
-                    self.actors
-                        .get(&id)
-                        .map(|a| a.user.keys().copied().collect::<Vec<_>>())
-                }) && !users
-                    .iter()
-                    .filter_map(|id| self.users.get(id))
-                    .any(|h| h.connected)
+                let actor_id = handle.actor_id;
+                if let Some(actor) = self.actors.get_mut(&actor_id) {
+                    // The closed socket has dropped its receiver. Any session
+                    // left is another device, or a reconnect that beat the
+                    // old socket's timeout.
+                    let sessions = actor.sessions.entry(user_id).or_default();
+                    sessions.retain(|session| !session.send.is_disconnected());
+                    if !sessions.is_empty() {
+                        set_active(sessions, self.config.session_policy);
+                        debug!(%user_id, %actor_id, sessions = sessions.len(), "Session closed");
+                        return;
+                    }
+                    actor.sessions.remove(&user_id);
+                    A::latency(&mut actor.shared, user_id, None);
+                    // The table may have been waiting on them alone.
+                    A::connected(&mut actor.shared, &mut actor.user, user_id, false);
+                    actor.dirty = true;
+                }
+                handle.connected = false;
+                debug!(%user_id, %actor_id, "User disconnected");
+                self.prune(actor_id);
+            }
+            JoinReq::Shutdown { reconnect_after } => self.shutdown(reconnect_after),
+            JoinReq::Admin(req) => self.admin(req),
+            JoinReq::Hint { user_id } => self.hint(user_id),
+            JoinReq::Latency { user_id, rtt } => {
+                if let Some(handle) = self.users.get(&user_id)
+                    && handle.connected
+                    && let Some(actor) = self.actors.get_mut(&handle.actor_id)
                 {
-                    let aid = actor_id.unwrap();
-                    users.iter().for_each(|id| {
-                        self.users.remove(id);
-                    });
-                    self.actors.remove(&aid);
+                    A::latency(&mut actor.shared, user_id, Some(rtt));
+                }
+            }
+        }
+    }
+    fn quick_match(
+        &mut self,
+        user_id: UserId,
+        preference: A::Preference,
+        send_server_msg: Outbox<A::Msg>,
+        send_sender: Sender<Sender<(A, UserId)>>,
+        active: Arc<AtomicBool>,
+    ) {
+        if self.users.contains_key(&user_id) {
+            // Already seated, so take them back to their game instead.
+            return self.join(JoinReq::Connect {
+                user_id,
+                actor_id: None,
+                send_server_msg,
+                send_sender,
+                active,
+            });
+        }
+        let Some((rules, players)) = A::match_rules(&self.rules, &preference) else {
+            // Dropping `send_sender` fails the join.
+            warn!(%user_id, ?preference, "Refused quick match with invalid preference");
+            return;
+        };
+        // Another device's place in the queue is given up for this one.
+        self.matchmaker.remove(&user_id);
+        let send_action =
+            self.matchmaker
+                .channel(&preference, rules, players, self.config.recv_bound);
+        if let Err(err) = send_sender.send(send_action) {
+            error!(%user_id, error = %err, "Failed to send action sender for quick match");
+            return;
+        }
+        let session = Session {
+            send: send_server_msg,
+            active,
+        };
+        info!(%user_id, ?preference, "User queued for quick match");
+        if let Some(group) = self.matchmaker.seat(user_id, session, &preference) {
+            self.form(group);
+        }
+    }
+    /// Turns a full quick-match group into a lobby and seats everyone in it.
+    fn form(&mut self, group: Forming<A>) {
+        let Forming {
+            rules,
+            seats,
+            send_action,
+            recv,
+            ..
+        } = group;
+        // Whatever was sent while waiting was meant for no game yet.
+        while let Ok(Some(_)) = recv.try_recv() {}
+        let actor_id = Uuid::now_v7();
+        let mut actor = Actor::matched(A::shared(&rules), send_action.clone(), recv);
+        seats.into_iter().for_each(|(user_id, session)| {
+            if let delivery @ (Delivery::Full | Delivery::Closed) =
+                session.send.push(A::join_msg(actor_id))
+            {
+                warn!(%user_id, %actor_id, ?delivery, "Failed to send join message for quick match");
+            }
+            actor.user.insert(user_id, Default::default());
+            A::joined(&mut actor.shared, user_id);
+            A::connected(&mut actor.shared, &mut actor.user, user_id, true);
+            actor.add_session(user_id, session, &self.config);
+            self.users.insert(
+                user_id,
+                UserHandle {
+                    connected: true,
+                    actor_id,
+                    send_action: send_action.clone(),
+                },
+            );
+            send_record(&self.record, Record::Join { user_id, actor_id });
+        });
+        counter!(telemetry::QUICK_MATCHES).increment(1);
+        info!(%actor_id, player_count = actor.user.len(), "Formed quick-match lobby");
+        self.actors.insert(actor_id, actor);
+        self.update_list();
+    }
+    fn hint(&mut self, user_id: UserId) {
+        let Some(actor) = self
+            .users
+            .get(&user_id)
+            .and_then(|h| self.actors.get(&h.actor_id))
+        else {
+            debug!(%user_id, "Hint requested by unknown user");
+            return;
+        };
+        let Some(msg) = A::hint(&actor.shared, &actor.user, user_id) else {
+            return;
+        };
+        actor.fan_out(&user_id, msg, |send, msg| match send.push(msg) {
+            Delivery::Queued | Delivery::Coalesced => counter!(telemetry::SERVER_MSGS).increment(1),
+            Delivery::Full => {
+                counter!(telemetry::SERVER_MSG_DROPS).increment(1);
+                warn!(%user_id, "Server message queue full, dropping hint");
+            }
+            Delivery::Closed => {
+                counter!(telemetry::SERVER_MSG_FAILURES).increment(1);
+                warn!(%user_id, "Failed to send hint, connection closed");
+            }
+        });
+    }
+    /// Removes the actor if none of its users are connected.
+    fn prune(&mut self, actor_id: ActorId) {
+        if let Some(actor) = self.actors.get(&actor_id)
+            && !actor
+                .user
+                .keys()
+                .filter_map(|id| self.users.get(id))
+                .any(|h| h.connected)
+        {
+            self.remove_actor(actor_id);
+            info!(%actor_id, "Removed actor - all users disconnected");
+        }
+    }
+    /// Takes the user out of their actor, dropping their connection.
+    fn evict(&mut self, user_id: UserId) -> Option<ActorId> {
+        let handle = self.users.remove(&user_id)?;
+        if let Some(actor) = self.actors.get_mut(&handle.actor_id) {
+            A::leave(&mut actor.shared, &mut actor.user, user_id);
+            actor.sessions.remove(&user_id);
+            actor.dirty = true;
+        }
+        Some(handle.actor_id)
+    }
+    fn admin(&mut self, req: AdminReq<A>) {
+        let sent = match req {
+            AdminReq::List { reply } => {
+                let list = self
+                    .actors
+                    .iter()
+                    .map(|(actor_id, actor)| ActorInfo {
+                        actor_id: *actor_id,
+                        status: A::status(&actor.shared),
+                        open: actor.open().is_some(),
+                        age_secs: actor.created.elapsed().as_secs(),
+                        players: actor
+                            .user
+                            .keys()
+                            .map(|user_id| PlayerInfo {
+                                user_id: *user_id,
+                                connected: self.users.get(user_id).is_some_and(|h| h.connected),
+                                sessions: actor.sessions.get(user_id).map_or(0, Vec::len),
+                            })
+                            .collect(),
+                    })
+                    .collect();
+                reply.send(list).is_ok()
+            }
+            AdminReq::Inspect { actor_id, reply } => {
+                let dump = self.actors.get(&actor_id).map(|actor| ActorDump {
+                    actor_id,
+                    shared: actor.shared.clone(),
+                    user: actor.user.clone(),
+                });
+                reply.send(dump).is_ok()
+            }
+            AdminReq::End { actor_id, reply } => {
+                let ended = self.actors.get_mut(&actor_id).is_some_and(|actor| {
+                    actor.dirty = true;
+                    A::end(&mut actor.shared)
+                });
+                info!(%actor_id, ended, "Admin ended game");
+                reply.send(ended).is_ok()
+            }
+            AdminReq::Close { actor_id, reply } => {
+                let closed = self.actors.contains_key(&actor_id);
+                self.remove_actor(actor_id);
+                info!(%actor_id, closed, "Admin closed actor");
+                reply.send(closed).is_ok()
+            }
+            AdminReq::Kick { user_id, reply } => {
+                let actor_id = self.evict(user_id);
+                if let Some(actor_id) = actor_id {
                     self.update_list();
-                    info!(actor_id = %aid, "Removed actor - all users disconnected");
+                    self.prune(actor_id);
                 }
+                info!(%user_id, kicked = actor_id.is_some(), "Admin kicked user");
+                reply.send(actor_id.is_some()).is_ok()
             }
+            AdminReq::Migrate {
+                user_id,
+                actor_id,
+                reply,
+            } => {
+                let res = self.migrate(user_id, actor_id);
+                info!(%user_id, %actor_id, result = ?res, "Admin migrated user");
+                reply.send(res).is_ok()
+            }
+        };
+        if !sent {
+            warn!("Admin request dropped before reply");
+        }
+    }
+    fn migrate(&mut self, user_id: UserId, actor_id: ActorId) -> Result<(), MigrateError> {
+        let from = self
+            .users
+            .get(&user_id)
+            .ok_or(MigrateError::UnknownUser)?
+            .actor_id;
+        if from == actor_id {
+            return Err(MigrateError::SameActor);
         }
+        let target = self
+            .actors
+            .get(&actor_id)
+            .ok_or(MigrateError::UnknownActor)?;
+        if !A::can_join(&target.shared, &target.user) {
+            return Err(MigrateError::LobbyClosed);
+        }
+        let send_action = target.send_action.clone();
+        self.evict(user_id);
+        if let Some(target) = self.actors.get_mut(&actor_id) {
+            target.user.insert(user_id, Default::default());
+            A::joined(&mut target.shared, user_id);
+            target.dirty = true;
+        }
+        self.users.insert(
+            user_id,
+            UserHandle {
+                connected: false,
+                actor_id,
+                send_action,
+            },
+        );
+        self.update_list();
+        self.prune(from);
+        Ok(())
     }
 }
 
@@ -222,33 +674,205 @@ pub struct Actor<A: Action> {
     recv: Receiver<(A, UserId)>,
     shared: A::Shared,
     user: HashMap<UserId, A::User>,
-    server_msgs: HashMap<UserId, Sender<A::Msg>>,
+    sessions: HashMap<UserId, Vec<Session<A>>>,
+    send_action: Sender<(A, UserId)>,
+    finished: bool,
+    /// The number of the last game recorded as dealt.
+    dealt: Option<u32>,
+    dirty: bool,
+    idle_since: Option<Instant>,
+    created: Instant,
+    last_bot: Instant,
 }
 
 impl<A: Action> Actor<A> {
-    fn open(&self) -> Option<u8> {
-        A::can_join(&self.shared, &self.user).then_some(self.user.len() as u8)
+    fn open(&self) -> Option<A::Listing> {
+        A::can_join(&self.shared, &self.user).then(|| A::listing(&self.shared, &self.user))
     }
-    fn spawn(user_id: UserId, send_server_msg: Sender<A::Msg>) -> (Self, Sender<(A, UserId)>) {
-        let shared = Default::default();
+    fn spawn(
+        user_id: UserId,
+        session: Session<A>,
+        mut shared: A::Shared,
+        recv_bound: usize,
+    ) -> (Self, Sender<(A, UserId)>) {
         let mut user = HashMap::default();
         user.insert(user_id, Default::default());
-        let mut server_msgs = HashMap::default();
-        server_msgs.insert(user_id, send_server_msg);
-        let (send, recv) = kanal::bounded(RECV_BOUND);
+        A::joined(&mut shared, user_id);
+        A::connected(&mut shared, &mut user, user_id, true);
+        let mut sessions = HashMap::default();
+        sessions.insert(user_id, vec![session]);
+        let (send, recv) = kanal::bounded(recv_bound);
         (
             Self {
                 recv,
                 shared,
                 user,
-                server_msgs,
+                sessions,
+                send_action: send.clone(),
+                finished: false,
+                dealt: None,
+                dirty: true,
+                idle_since: None,
+                created: Instant::now(),
+                last_bot: Instant::now(),
             },
             send,
         )
     }
-    fn update(&mut self) {
+    /// An empty actor on the channel its quick-match players already hold.
+    fn matched(
+        shared: A::Shared,
+        send_action: Sender<(A, UserId)>,
+        recv: Receiver<(A, UserId)>,
+    ) -> Self {
+        Self {
+            recv,
+            shared,
+            user: HashMap::default(),
+            sessions: HashMap::default(),
+            send_action,
+            finished: false,
+            dealt: None,
+            dirty: true,
+            idle_since: None,
+            created: Instant::now(),
+            last_bot: Instant::now(),
+        }
+    }
+    fn restore(
+        shared: A::Shared,
+        user: HashMap<UserId, A::User>,
+        recv_bound: usize,
+    ) -> (Self, Sender<(A, UserId)>) {
+        let (send, recv) = kanal::bounded(recv_bound);
+        // A game that finished before the restart was already recorded.
+        let finished = A::summary(&shared, &user).is_some();
+        let dealt = A::dealt(&shared).map(|(game, _)| game);
+        (
+            Self {
+                recv,
+                shared,
+                user,
+                sessions: HashMap::default(),
+                send_action: send.clone(),
+                finished,
+                dealt,
+                dirty: false,
+                idle_since: Some(Instant::now()),
+                created: Instant::now(),
+                last_bot: Instant::now(),
+            },
+            send,
+        )
+    }
+    /// Adds a connection for `user_id`, closing the oldest past the limit,
+    /// and returns how many the user now has.
+    fn add_session(&mut self, user_id: UserId, session: Session<A>, config: &ActorConfig) -> usize {
+        let sessions = self.sessions.entry(user_id).or_default();
+        sessions.push(session);
+        // Dropping an outbox ends that socket's writer, which closes it.
+        let excess = sessions.len().saturating_sub(config.max_sessions);
+        sessions.drain(..excess);
+        set_active(sessions, config.session_policy);
+        sessions.len()
+    }
+    /// Hands `msg` to `send` once per session of `user_id`, cloning it for
+    /// all but the last.
+    fn fan_out(
+        &self,
+        user_id: &UserId,
+        msg: A::Msg,
+        mut send: impl FnMut(&Outbox<A::Msg>, A::Msg),
+    ) {
+        if let Some((last, rest)) = self.sessions.get(user_id).and_then(|s| s.split_last()) {
+            rest.iter()
+                .for_each(|session| send(&session.send, msg.clone()));
+            send(&last.send, msg);
+        }
+    }
+    fn update(&mut self, actor_id: ActorId, record: &Sender<Record<A>>) {
+        // Leaving or disconnecting can start a rematch between ticks.
+        self.record_deal(actor_id, record);
         while let Ok(Some((msg, user_id))) = self.recv.try_recv() {
+            send_record(
+                record,
+                Record::Action {
+                    actor_id,
+                    user_id,
+                    action: msg.clone(),
+                },
+            );
+            let start = Instant::now();
             Action::update(msg, &mut self.shared, &mut self.user, user_id);
+            histogram!(telemetry::ACTION_SECONDS).record(start.elapsed());
+            self.record_deal(actor_id, record);
+            counter!(telemetry::ACTIONS).increment(1);
+            self.dirty = true;
+        }
+    }
+    /// Records a game dealt since the last call, so its replay starts with
+    /// the next recorded action.
+    fn record_deal(&mut self, actor_id: ActorId, record: &Sender<Record<A>>) {
+        if let Some((game, seed)) = A::dealt(&self.shared)
+            && self.dealt != Some(game)
+        {
+            self.dealt = Some(game);
+            send_record(
+                record,
+                Record::Dealt {
+                    actor_id,
+                    game,
+                    seed,
+                },
+            );
+        }
+    }
+    /// Queues at most one bot move per `delay`, so humans can follow along.
+    fn bots(&mut self, actor_id: ActorId, delay: Duration) {
+        if self.last_bot.elapsed() < delay {
+            return;
+        }
+        if let Some((user_id, action)) = A::bots(&self.shared, &self.user).into_iter().next() {
+            self.last_bot = Instant::now();
+            match self.send_action.try_send((action, user_id)) {
+                Ok(true) => debug!(%actor_id, %user_id, "Queued bot action"),
+                Ok(false) => warn!(%actor_id, %user_id, "Action channel full, dropping bot action"),
+                Err(err) => warn!(%actor_id, %user_id, error = %err, "Failed to queue bot action"),
+            }
+        }
+    }
+}
+
+/// Tells each of a user's sessions, oldest first, whether it may act.
+fn set_active<A: Action>(sessions: &[Session<A>], policy: SessionPolicy) {
+    sessions.iter().enumerate().for_each(|(i, session)| {
+        let active = policy.may_act(i, sessions.len());
+        session.active.store(active, Ordering::Relaxed);
+    });
+}
+
+/// Hands a record to the storage thread. The channel is unbounded: a single
+/// dropped action would leave a replay that no longer follows its seed.
+fn send_record<A: Action>(record: &Sender<Record<A>>, rec: Record<A>) {
+    if let Err(err) = record.send(rec) {
+        error!(error = %err, "Failed to send record, storage has stopped");
+    }
+}
+
+/// One connection of a user. A user may hold several, one per device.
+pub struct Session<A: Action> {
+    pub(crate) send: Outbox<A::Msg>,
+    /// Read by the connection before forwarding actions; cleared when the
+    /// session policy leaves this session watching only.
+    active: Arc<AtomicBool>,
+}
+
+#[cfg(test)]
+impl<A: Action> Session<A> {
+    pub(crate) fn new(send: Outbox<A::Msg>) -> Self {
+        Self {
+            send,
+            active: Arc::new(AtomicBool::new(true)),
         }
     }
 }
@@ -263,10 +887,35 @@ pub enum JoinReq<A: Action> {
     Connect {
         user_id: UserId,
         actor_id: Option<ActorId>,
-        send_server_msg: Sender<A::Msg>,
+        send_server_msg: Outbox<A::Msg>,
         send_sender: Sender<Sender<(A, UserId)>>,
+        /// Whether this connection may send actions, kept up to date by the
+        /// actor system as the user's other sessions come and go.
+        active: Arc<AtomicBool>,
     },
     Disconnect {
         user_id: UserId,
     },
+    Shutdown {
+        reconnect_after: Duration,
+    },
+    Admin(AdminReq<A>),
+    /// Queues the user for a quick match. Answered with the action channel
+    /// at once, queue positions while waiting, then the lobby's join message.
+    QuickMatch {
+        user_id: UserId,
+        preference: A::Preference,
+        send_server_msg: Outbox<A::Msg>,
+        send_sender: Sender<Sender<(A, UserId)>>,
+        active: Arc<AtomicBool>,
+    },
+    /// Asks for suggested moves, answered on the user's message channel.
+    Hint {
+        user_id: UserId,
+    },
+    /// A heartbeat round trip measured by the user's connection.
+    Latency {
+        user_id: UserId,
+        rtt: Duration,
+    },
 }
@@ -0,0 +1,69 @@
+use crate::{Action, ActorId, UserId};
+use serde::Serialize;
+use std::collections::HashMap;
+use tokio::sync::oneshot;
+
+/// Operator requests, answered by the actor thread on the enclosed channel.
+pub enum AdminReq<A: Action> {
+    List {
+        reply: oneshot::Sender<Vec<ActorInfo>>,
+    },
+    Inspect {
+        actor_id: ActorId,
+        reply: oneshot::Sender<Option<ActorDump<A>>>,
+    },
+    /// Ends the game in place, as if the party had lost.
+    End {
+        actor_id: ActorId,
+        reply: oneshot::Sender<bool>,
+    },
+    /// Removes the actor and drops every connection to it.
+    Close {
+        actor_id: ActorId,
+        reply: oneshot::Sender<bool>,
+    },
+    Kick {
+        user_id: UserId,
+        reply: oneshot::Sender<bool>,
+    },
+    /// Moves the user into another open lobby. Their connection is dropped so
+    /// the client reconnects into the new actor.
+    Migrate {
+        user_id: UserId,
+        actor_id: ActorId,
+        reply: oneshot::Sender<Result<(), MigrateError>>,
+    },
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ActorInfo {
+    pub actor_id: ActorId,
+    pub status: &'static str,
+    pub open: bool,
+    pub age_secs: u64,
+    pub players: Vec<PlayerInfo>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PlayerInfo {
+    pub user_id: UserId,
+    pub connected: bool,
+    /// Open connections, one per device.
+    pub sessions: usize,
+}
+
+#[derive(Serialize)]
+#[serde(bound = "")]
+pub struct ActorDump<A: Action> {
+    pub actor_id: ActorId,
+    pub shared: A::Shared,
+    pub user: HashMap<UserId, A::User>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub enum MigrateError {
+    UnknownUser,
+    UnknownActor,
+    SameActor,
+    LobbyClosed,
+}
@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Tuning for the actor thread.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ActorConfig {
+    /// Milliseconds between actor system ticks.
+    pub tick_ms: u64,
+    /// Capacity of each actor's action channel.
+    pub recv_bound: usize,
+    /// Seconds between snapshots of changed actors.
+    pub snapshot_interval_secs: u64,
+    /// Seconds a restored actor waits for its first user to reconnect.
+    pub restore_timeout_secs: u64,
+    /// Milliseconds between moves made by bot players.
+    pub bot_delay_ms: u64,
+    /// Connections one user may hold at once, e.g. a phone and a laptop.
+    /// Opening another closes the oldest.
+    pub max_sessions: usize,
+    /// Which of a user's connections may send actions.
+    pub session_policy: SessionPolicy,
+}
+
+/// Which of a user's sessions may act. The others still see the table.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionPolicy {
+    /// Every session may act.
+    #[default]
+    All,
+    /// Only the most recently opened session may act.
+    Latest,
+}
+
+impl SessionPolicy {
+    /// Whether the session at `index`, oldest first, of `count` may act.
+    pub fn may_act(self, index: usize, count: usize) -> bool {
+        match self {
+            Self::All => true,
+            Self::Latest => index + 1 == count,
+        }
+    }
+}
+
+impl Default for ActorConfig {
+    fn default() -> Self {
+        Self {
+            tick_ms: 10,
+            recv_bound: 128,
+            snapshot_interval_secs: 5,
+            restore_timeout_secs: 600,
+            bot_delay_ms: 800,
+            max_sessions: 4,
+            session_policy: SessionPolicy::All,
+        }
+    }
+}
+
+impl ActorConfig {
+    pub fn tick(&self) -> Duration {
+        Duration::from_millis(self.tick_ms)
+    }
+    pub fn snapshot_interval(&self) -> Duration {
+        Duration::from_secs(self.snapshot_interval_secs)
+    }
+    pub fn restore_timeout(&self) -> Duration {
+        Duration::from_secs(self.restore_timeout_secs)
+    }
+    pub fn bot_delay(&self) -> Duration {
+        Duration::from_millis(self.bot_delay_ms)
+    }
+}
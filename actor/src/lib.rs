@@ -1,5 +1,19 @@
 pub mod action;
 pub mod actor;
+pub mod admin;
+pub mod config;
+pub mod list;
+pub mod mailbox;
+mod matchmaking;
+pub mod record;
+pub mod snapshot;
+pub mod telemetry;
 
 pub use action::*;
 pub use actor::*;
+pub use admin::*;
+pub use config::*;
+pub use list::*;
+pub use mailbox::*;
+pub use record::*;
+pub use snapshot::*;
@@ -0,0 +1,109 @@
+use crate::ActorId;
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+use tokio::sync::{RwLock, broadcast};
+use ts_rs::TS;
+
+/// Changes kept for subscribers that fall behind before they must resync.
+const EVENT_BOUND: usize = 256;
+
+/// A change to the list of open lobbies.
+#[derive(Clone, Debug, PartialEq, Serialize, TS)]
+#[ts(export, export_to = "../../frontend/src/bindings/")]
+pub enum LobbyEvent<L> {
+    /// The lobby became joinable.
+    Created {
+        actor_id: ActorId,
+        lobby: L,
+    },
+    Updated {
+        actor_id: ActorId,
+        lobby: L,
+    },
+    /// The lobby started, filled up or went away.
+    Closed {
+        actor_id: ActorId,
+    },
+}
+
+/// Open lobbies, written by the actor system and read by request handlers.
+/// Every write is also published as [`LobbyEvent`]s for live subscribers.
+#[derive(Clone, Debug)]
+pub struct ActorList<L> {
+    lobbies: Arc<RwLock<Vec<(ActorId, L)>>>,
+    /// Taken on shutdown, which ends every subscription.
+    events: Arc<Mutex<Option<broadcast::Sender<LobbyEvent<L>>>>>,
+}
+
+impl<L: Clone> Default for ActorList<L> {
+    fn default() -> Self {
+        Self {
+            lobbies: Default::default(),
+            events: Arc::new(Mutex::new(Some(broadcast::channel(EVENT_BOUND).0))),
+        }
+    }
+}
+
+impl<L: Clone + PartialEq> ActorList<L> {
+    pub async fn read(&self) -> Vec<(ActorId, L)> {
+        self.lobbies.read().await.clone()
+    }
+    /// The current lobbies and a receiver for every change after them.
+    pub async fn subscribe(&self) -> (Vec<(ActorId, L)>, broadcast::Receiver<LobbyEvent<L>>) {
+        let lock = self.lobbies.read().await;
+        let recv = match self.events().as_ref() {
+            Some(events) => events.subscribe(),
+            // Already shut down: a receiver that reports closed at once.
+            None => broadcast::channel(1).1,
+        };
+        (lock.clone(), recv)
+    }
+    pub fn write(&self, list: Vec<(ActorId, L)>) {
+        let mut lock = self.lobbies.blocking_write();
+        // Published under the lock, so a subscriber's snapshot and its first
+        // event never overlap.
+        if let Some(events) = self.events().as_ref()
+            && events.receiver_count() > 0
+        {
+            for event in diff(&lock, &list) {
+                let _ = events.send(event);
+            }
+        }
+        *lock = list;
+    }
+    /// Ends every subscription, so open streams do not hold up shutdown.
+    pub fn close(&self) {
+        self.events().take();
+    }
+    fn events(&self) -> MutexGuard<'_, Option<broadcast::Sender<LobbyEvent<L>>>> {
+        self.events.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn diff<L: Clone + PartialEq>(old: &[(ActorId, L)], new: &[(ActorId, L)]) -> Vec<LobbyEvent<L>> {
+    let before: HashMap<_, _> = old.iter().map(|(id, lobby)| (*id, lobby)).collect();
+    let after: HashMap<_, _> = new.iter().map(|(id, lobby)| (*id, lobby)).collect();
+    let closed = old
+        .iter()
+        .filter(|(id, _)| !after.contains_key(id))
+        .map(|(actor_id, _)| LobbyEvent::Closed {
+            actor_id: *actor_id,
+        });
+    let changed = new
+        .iter()
+        .filter_map(|(actor_id, lobby)| match before.get(actor_id) {
+            None => Some(LobbyEvent::Created {
+                actor_id: *actor_id,
+                lobby: lobby.clone(),
+            }),
+            Some(old) if *old != lobby => Some(LobbyEvent::Updated {
+                actor_id: *actor_id,
+                lobby: lobby.clone(),
+            }),
+            Some(_) => None,
+        });
+    closed.chain(changed).collect()
+}
@@ -0,0 +1,197 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+use tokio::sync::Notify;
+
+/// Creates a connection's message channel: up to `bound` queued messages plus
+/// the latest state snapshot. The actor side never blocks, so a slow client
+/// can only ever fall behind on its own table.
+pub fn mailbox<M>(bound: usize) -> (Outbox<M>, Inbox<M>) {
+    let shared = Arc::new(Shared {
+        slots: Mutex::new(Slots {
+            queue: VecDeque::with_capacity(bound),
+            latest: None,
+            outbox: true,
+            inbox: true,
+        }),
+        notify: Notify::new(),
+        bound,
+    });
+    (Outbox(shared.clone()), Inbox(shared))
+}
+
+/// What became of a message handed to an [`Outbox`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Delivery {
+    Queued,
+    /// Took the place of a snapshot the connection had not sent yet.
+    Coalesced,
+    /// The queue was full and the message was dropped.
+    Full,
+    /// The connection has dropped its [`Inbox`].
+    Closed,
+}
+
+struct Shared<M> {
+    slots: Mutex<Slots<M>>,
+    notify: Notify,
+    bound: usize,
+}
+
+struct Slots<M> {
+    queue: VecDeque<M>,
+    latest: Option<M>,
+    outbox: bool,
+    inbox: bool,
+}
+
+impl<M> Shared<M> {
+    fn lock(&self) -> MutexGuard<'_, Slots<M>> {
+        self.slots.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// The actor's end of a connection. Dropping it ends the connection once its
+/// remaining messages are taken.
+pub struct Outbox<M>(Arc<Shared<M>>);
+
+impl<M> Outbox<M> {
+    /// Queues a message that must not be merged with others, such as a join
+    /// confirmation or a hint.
+    pub fn push(&self, msg: M) -> Delivery {
+        let mut slots = self.0.lock();
+        if !slots.inbox {
+            return Delivery::Closed;
+        }
+        if slots.queue.len() >= self.0.bound {
+            return Delivery::Full;
+        }
+        slots.queue.push_back(msg);
+        drop(slots);
+        self.0.notify.notify_one();
+        Delivery::Queued
+    }
+    /// Sets the state snapshot to send after any queued messages, replacing
+    /// one that has not gone out yet.
+    pub fn replace(&self, msg: M) -> Delivery {
+        let mut slots = self.0.lock();
+        if !slots.inbox {
+            return Delivery::Closed;
+        }
+        let delivery = match slots.latest.replace(msg) {
+            Some(_) => Delivery::Coalesced,
+            None => Delivery::Queued,
+        };
+        drop(slots);
+        self.0.notify.notify_one();
+        delivery
+    }
+    pub fn is_disconnected(&self) -> bool {
+        !self.0.lock().inbox
+    }
+}
+
+impl<M> Drop for Outbox<M> {
+    fn drop(&mut self) {
+        self.0.lock().outbox = false;
+        self.0.notify.notify_one();
+    }
+}
+
+/// The connection's end, read by a single writer.
+pub struct Inbox<M>(Arc<Shared<M>>);
+
+impl<M> Inbox<M> {
+    /// Waits for the next message, queued ones before the snapshot. Returns
+    /// `None` once the outbox is dropped and drained. Cancel safe: nothing is
+    /// taken until the future resolves.
+    pub async fn recv(&self) -> Option<M> {
+        loop {
+            if let Some(msg) = self.try_recv() {
+                return Some(msg);
+            }
+            if !self.0.lock().outbox {
+                return None;
+            }
+            // A wake-up sent since the check is kept as a permit.
+            self.0.notify.notified().await;
+        }
+    }
+    pub fn try_recv(&self) -> Option<M> {
+        let mut slots = self.0.lock();
+        slots.queue.pop_front().or_else(|| slots.latest.take())
+    }
+}
+
+impl<M> Drop for Inbox<M> {
+    fn drop(&mut self) {
+        self.0.lock().inbox = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshots_coalesce_behind_queued_messages() {
+        let (outbox, inbox) = mailbox(4);
+        assert_eq!(outbox.replace(1), Delivery::Queued);
+        assert_eq!(outbox.push(10), Delivery::Queued);
+        assert_eq!(outbox.replace(2), Delivery::Coalesced);
+        assert_eq!(outbox.push(11), Delivery::Queued);
+        assert_eq!(outbox.replace(3), Delivery::Coalesced);
+        let got: Vec<_> = std::iter::from_fn(|| inbox.try_recv()).collect();
+        assert_eq!(got, [10, 11, 3]);
+    }
+
+    #[test]
+    fn a_taken_snapshot_is_not_coalesced() {
+        let (outbox, inbox) = mailbox(4);
+        outbox.replace(1);
+        assert_eq!(inbox.try_recv(), Some(1));
+        assert_eq!(outbox.replace(2), Delivery::Queued);
+        assert_eq!(inbox.try_recv(), Some(2));
+        assert_eq!(inbox.try_recv(), None);
+    }
+
+    #[test]
+    fn a_full_queue_drops_pushes_but_keeps_the_snapshot() {
+        let (outbox, inbox) = mailbox(1);
+        assert_eq!(outbox.push(10), Delivery::Queued);
+        assert_eq!(outbox.push(11), Delivery::Full);
+        assert_eq!(outbox.replace(1), Delivery::Queued);
+        let got: Vec<_> = std::iter::from_fn(|| inbox.try_recv()).collect();
+        assert_eq!(got, [10, 1]);
+    }
+
+    #[test]
+    fn a_dropped_inbox_closes_the_outbox() {
+        let (outbox, inbox) = mailbox::<u8>(1);
+        drop(inbox);
+        assert!(outbox.is_disconnected());
+        assert_eq!(outbox.push(1), Delivery::Closed);
+        assert_eq!(outbox.replace(1), Delivery::Closed);
+    }
+
+    #[tokio::test]
+    async fn recv_drains_before_reporting_the_close() {
+        let (outbox, inbox) = mailbox(2);
+        outbox.push(10);
+        outbox.replace(1);
+        drop(outbox);
+        assert_eq!(inbox.recv().await, Some(10));
+        assert_eq!(inbox.recv().await, Some(1));
+        assert_eq!(inbox.recv().await, None);
+    }
+
+    #[tokio::test]
+    async fn recv_wakes_for_a_later_snapshot() {
+        let (outbox, inbox) = mailbox(2);
+        let recv = tokio::spawn(async move { inbox.recv().await });
+        tokio::task::yield_now().await;
+        outbox.replace(7);
+        assert_eq!(recv.await.unwrap(), Some(7));
+    }
+}
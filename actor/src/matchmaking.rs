@@ -0,0 +1,277 @@
+use crate::{Action, Delivery, Session, UserId};
+use kanal::{Receiver, Sender};
+use tracing::debug;
+
+/// Players waiting for a quick match with the same preference. They already
+/// hold the action channel of the lobby they will form, so their connections
+/// work as if seated while they wait.
+pub(crate) struct Forming<A: Action> {
+    pub preference: A::Preference,
+    pub rules: A::Rules,
+    pub players: usize,
+    pub seats: Vec<(UserId, Session<A>)>,
+    pub send_action: Sender<(A, UserId)>,
+    pub recv: Receiver<(A, UserId)>,
+}
+
+impl<A: Action> Forming<A> {
+    /// Tells everyone in the group where they stand.
+    fn notify(&self) {
+        let waiting = self.seats.len();
+        self.seats
+            .iter()
+            .enumerate()
+            .for_each(|(i, (user_id, session))| {
+                let msg = A::queued_msg(i + 1, waiting, self.players);
+                if let delivery @ (Delivery::Full | Delivery::Closed) = session.send.push(msg) {
+                    debug!(%user_id, ?delivery, "Failed to send queue position");
+                }
+            });
+    }
+}
+
+/// The quick-match queue: one forming lobby per preference, filled in the
+/// order players asked.
+pub(crate) struct Matchmaker<A: Action> {
+    forming: Vec<Forming<A>>,
+}
+
+impl<A: Action> Default for Matchmaker<A> {
+    fn default() -> Self {
+        Self {
+            forming: Vec::new(),
+        }
+    }
+}
+
+impl<A: Action> Matchmaker<A> {
+    /// The action channel of the lobby forming for `preference`, opening one
+    /// if nobody is waiting for it yet.
+    pub fn channel(
+        &mut self,
+        preference: &A::Preference,
+        rules: A::Rules,
+        players: usize,
+        recv_bound: usize,
+    ) -> Sender<(A, UserId)> {
+        if let Some(group) = self.group(preference) {
+            return group.send_action.clone();
+        }
+        let (send_action, recv) = kanal::bounded(recv_bound);
+        self.forming.push(Forming {
+            preference: preference.clone(),
+            rules,
+            players,
+            seats: Vec::new(),
+            send_action: send_action.clone(),
+            recv,
+        });
+        send_action
+    }
+    /// Queues `user_id` behind the others with the same preference, and
+    /// hands back the group once it is large enough to play.
+    pub fn seat(
+        &mut self,
+        user_id: UserId,
+        session: Session<A>,
+        preference: &A::Preference,
+    ) -> Option<Forming<A>> {
+        let index = self
+            .forming
+            .iter()
+            .position(|group| group.preference == *preference)?;
+        let group = &mut self.forming[index];
+        group.seats.push((user_id, session));
+        if group.seats.len() >= group.players {
+            return Some(self.forming.swap_remove(index));
+        }
+        group.notify();
+        None
+    }
+    /// Takes `user_id` out of the queue, closing their waiting connection.
+    pub fn remove(&mut self, user_id: &UserId) -> bool {
+        self.retain(|id, _| id != user_id)
+    }
+    /// Drops `user_id`'s waiting connection if it has closed.
+    pub fn disconnect(&mut self, user_id: &UserId) -> bool {
+        self.retain(|id, session| id != user_id || !session.send.is_disconnected())
+    }
+    pub fn waiting(&self) -> usize {
+        self.forming.iter().map(|group| group.seats.len()).sum()
+    }
+    pub fn sessions(&self) -> impl Iterator<Item = &(UserId, Session<A>)> {
+        self.forming.iter().flat_map(|group| group.seats.iter())
+    }
+    fn group(&self, preference: &A::Preference) -> Option<&Forming<A>> {
+        self.forming
+            .iter()
+            .find(|group| group.preference == *preference)
+    }
+    /// Keeps the seats `keep` accepts, updating the positions of groups that
+    /// changed and dropping groups left empty. Returns whether any changed.
+    fn retain(&mut self, mut keep: impl FnMut(&UserId, &Session<A>) -> bool) -> bool {
+        let mut changed = false;
+        self.forming.iter_mut().for_each(|group| {
+            let before = group.seats.len();
+            group.seats.retain(|(id, session)| keep(id, session));
+            if group.seats.len() != before {
+                changed = true;
+                group.notify();
+            }
+        });
+        self.forming.retain(|group| !group.seats.is_empty());
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ActorId, Inbox, mailbox};
+    use serde::{Deserialize, Serialize};
+    use std::{collections::HashMap, time::Duration};
+    use ts_rs::TS;
+
+    /// Just enough of a game to queue for: the preference is the table size
+    /// and messages are plain text.
+    #[derive(Clone, Debug, Serialize, Deserialize, TS)]
+    struct Nop;
+
+    impl Action for Nop {
+        type Shared = ();
+        type User = ();
+        type Msg = String;
+        type Summary = ();
+        type Rules = ();
+        type Listing = ();
+        type Preference = u8;
+        fn can_join(_: &(), _: &HashMap<UserId, ()>) -> bool {
+            true
+        }
+        fn listing(_: &(), _: &HashMap<UserId, ()>) {}
+        fn match_rules(_: &(), players: &u8) -> Option<((), usize)> {
+            Some(((), *players as usize))
+        }
+        fn queued_msg(position: usize, waiting: usize, players: usize) -> String {
+            format!("{position}/{waiting}/{players}")
+        }
+        fn shared(_: &()) {}
+        fn update(self, _: &mut (), _: &mut HashMap<UserId, ()>, _: UserId) {}
+        fn join_msg(actor_id: ActorId) -> String {
+            actor_id.to_string()
+        }
+        fn shutdown_msg(_: Duration) -> String {
+            String::new()
+        }
+        fn incompatible_msg(_: u16, _: u16) -> String {
+            String::new()
+        }
+        fn downgrade(msg: String, _: u16) -> String {
+            msg
+        }
+        fn msg(_: &(), _: &HashMap<UserId, ()>) -> Vec<(UserId, String)> {
+            Vec::new()
+        }
+        fn joined(_: &mut (), _: UserId) {}
+        fn connected(_: &mut (), _: &mut HashMap<UserId, ()>, _: UserId, _: bool) {}
+        fn bots(_: &(), _: &HashMap<UserId, ()>) -> Vec<(UserId, Self)> {
+            Vec::new()
+        }
+        fn latency(_: &mut (), _: UserId, _: Option<Duration>) {}
+        fn leave(_: &mut (), _: &mut HashMap<UserId, ()>, _: UserId) {}
+        fn end(_: &mut ()) -> bool {
+            false
+        }
+        fn hint(_: &(), _: &HashMap<UserId, ()>, _: UserId) -> Option<String> {
+            None
+        }
+        fn status(_: &()) -> &'static str {
+            ""
+        }
+        fn dealt(_: &()) -> Option<(u32, u64)> {
+            None
+        }
+        fn summary(_: &(), _: &HashMap<UserId, ()>) -> Option<()> {
+            None
+        }
+    }
+
+    fn user(name: &str) -> UserId {
+        UserId::from(name).unwrap()
+    }
+
+    /// Queues `name` for a table of `players`, returning their inbox and the
+    /// group if they completed it.
+    fn queue(
+        matchmaker: &mut Matchmaker<Nop>,
+        name: &str,
+        players: u8,
+    ) -> (Inbox<String>, Option<Forming<Nop>>) {
+        let (send, recv) = mailbox(8);
+        matchmaker.channel(&players, (), players as usize, 8);
+        let group = matchmaker.seat(user(name), Session::new(send), &players);
+        (recv, group)
+    }
+
+    fn drain(inbox: &Inbox<String>) -> Vec<String> {
+        std::iter::from_fn(|| inbox.try_recv()).collect()
+    }
+
+    #[test]
+    fn a_group_forms_once_enough_players_share_a_preference() {
+        let mut matchmaker = Matchmaker::default();
+        let (ann, group) = queue(&mut matchmaker, "ann", 3);
+        assert!(group.is_none());
+        let (_bob, group) = queue(&mut matchmaker, "bob", 2);
+        assert!(group.is_none());
+        let (_cat, group) = queue(&mut matchmaker, "cat", 3);
+        assert!(group.is_none());
+        assert_eq!(matchmaker.waiting(), 3);
+        assert_eq!(drain(&ann), ["1/1/3", "1/2/3"]);
+
+        let (_dan, group) = queue(&mut matchmaker, "dan", 3);
+        let group = group.expect("three players asked for three seats");
+        let seated: Vec<_> = group.seats.iter().map(|(id, _)| *id).collect();
+        assert_eq!(seated, [user("ann"), user("cat"), user("dan")]);
+        assert_eq!(matchmaker.waiting(), 1);
+    }
+
+    #[test]
+    fn a_group_shares_one_action_channel() {
+        let mut matchmaker = Matchmaker::<Nop>::default();
+        let first = matchmaker.channel(&2, (), 2, 8);
+        let second = matchmaker.channel(&2, (), 2, 8);
+        let other = matchmaker.channel(&3, (), 3, 8);
+        [first, second, other]
+            .iter()
+            .for_each(|send| send.send((Nop, user("ann"))).unwrap());
+        let (_, group) = queue(&mut matchmaker, "ann", 2);
+        assert!(group.is_none());
+        let (_, group) = queue(&mut matchmaker, "bob", 2);
+        assert_eq!(group.unwrap().recv.len(), 2);
+    }
+
+    #[test]
+    fn leaving_moves_the_queue_up() {
+        let mut matchmaker = Matchmaker::default();
+        let (_ann, _) = queue(&mut matchmaker, "ann", 3);
+        let (bob, _) = queue(&mut matchmaker, "bob", 3);
+        drain(&bob);
+        assert!(matchmaker.remove(&user("ann")));
+        assert_eq!(drain(&bob), ["1/1/3"]);
+        assert!(!matchmaker.remove(&user("ann")));
+        assert!(matchmaker.remove(&user("bob")));
+        assert_eq!(matchmaker.waiting(), 0);
+        assert!(matchmaker.forming.is_empty());
+    }
+
+    #[test]
+    fn only_closed_connections_are_dropped_on_disconnect() {
+        let mut matchmaker = Matchmaker::default();
+        let (ann, _) = queue(&mut matchmaker, "ann", 3);
+        assert!(!matchmaker.disconnect(&user("ann")));
+        drop(ann);
+        assert!(matchmaker.disconnect(&user("ann")));
+        assert_eq!(matchmaker.waiting(), 0);
+    }
+}
@@ -0,0 +1,30 @@
+use crate::{Action, ActorId, UserId};
+
+/// Events emitted by the actor system for persistence.
+pub enum Record<A: Action> {
+    Join {
+        user_id: UserId,
+        actor_id: ActorId,
+    },
+    Action {
+        actor_id: ActorId,
+        user_id: UserId,
+        action: A,
+    },
+    /// Game number `game` was dealt; `seed` with the actions recorded after
+    /// it plays the game out again.
+    Dealt {
+        actor_id: ActorId,
+        game: u32,
+        seed: u64,
+    },
+    Finished {
+        actor_id: ActorId,
+        game: u32,
+        players: Vec<UserId>,
+        summary: A::Summary,
+    },
+    Closed {
+        actor_id: ActorId,
+    },
+}
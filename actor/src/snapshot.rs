@@ -0,0 +1,84 @@
+use crate::{Action, ActorId, UserId};
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, Write},
+    path::PathBuf,
+};
+use tracing::{error, info, warn};
+
+const EXTENSION: &str = "snap";
+
+/// Directory holding one postcard encoded snapshot per live actor.
+/// Files are written to a temporary path and renamed into place, so a crash
+/// mid-write leaves the previous snapshot intact.
+#[derive(Clone, Debug)]
+pub struct SnapshotDir {
+    path: PathBuf,
+}
+
+impl SnapshotDir {
+    pub fn new(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        fs::create_dir_all(&path)?;
+        Ok(Self { path })
+    }
+    fn file(&self, actor_id: ActorId) -> PathBuf {
+        self.path.join(format!("{actor_id}.{EXTENSION}"))
+    }
+    pub fn save<A: Action>(
+        &self,
+        actor_id: ActorId,
+        shared: &A::Shared,
+        user: &HashMap<UserId, A::User>,
+    ) -> io::Result<()> {
+        let bytes = postcard::to_stdvec(&(shared, user)).map_err(io::Error::other)?;
+        let file = self.file(actor_id);
+        let tmp = file.with_extension("tmp");
+        let mut out = File::create(&tmp)?;
+        out.write_all(&bytes)?;
+        out.sync_all()?;
+        fs::rename(&tmp, &file)
+    }
+    pub fn remove(&self, actor_id: ActorId) {
+        let file = self.file(actor_id);
+        if let Err(err) = fs::remove_file(&file)
+            && err.kind() != io::ErrorKind::NotFound
+        {
+            warn!(%actor_id, error = %err, "Failed to remove snapshot");
+        }
+    }
+    /// Loads every readable snapshot; unreadable ones are logged and skipped.
+    #[allow(clippy::type_complexity)]
+    pub fn load<A: Action>(&self) -> Vec<(ActorId, A::Shared, HashMap<UserId, A::User>)> {
+        let entries = match fs::read_dir(&self.path) {
+            Ok(entries) => entries,
+            Err(err) => {
+                error!(path = %self.path.display(), error = %err, "Failed to read snapshot directory");
+                return Vec::new();
+            }
+        };
+        let snapshots: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == EXTENSION))
+            .filter_map(|path| {
+                let actor_id = path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .and_then(|stem| ActorId::parse_str(stem).ok())?;
+                match fs::read(&path)
+                    .map_err(|err| err.to_string())
+                    .and_then(|bytes| postcard::from_bytes(&bytes).map_err(|err| err.to_string()))
+                {
+                    Ok((shared, user)) => Some((actor_id, shared, user)),
+                    Err(err) => {
+                        error!(%actor_id, error = %err, "Failed to load snapshot");
+                        None
+                    }
+                }
+            })
+            .collect();
+        info!(count = snapshots.len(), "Loaded snapshots");
+        snapshots
+    }
+}
@@ -0,0 +1,42 @@
+use metrics::{Unit, describe_counter, describe_gauge, describe_histogram};
+
+pub const ACTORS: &str = "regicide_actors";
+pub const USERS: &str = "regicide_users";
+pub const SESSIONS: &str = "regicide_sessions";
+pub const QUEUED: &str = "regicide_queued_players";
+pub const QUICK_MATCHES: &str = "regicide_quick_matches_total";
+pub const GAMES: &str = "regicide_games";
+pub const TICK_SECONDS: &str = "regicide_tick_seconds";
+pub const ACTION_SECONDS: &str = "regicide_action_seconds";
+pub const ACTIONS: &str = "regicide_actions_total";
+pub const SERVER_MSGS: &str = "regicide_server_msgs_total";
+pub const SERVER_MSG_FAILURES: &str = "regicide_server_msg_failures_total";
+pub const SERVER_MSG_DROPS: &str = "regicide_server_msg_drops_total";
+pub const SERVER_MSG_COALESCED: &str = "regicide_server_msgs_coalesced_total";
+
+/// Registers descriptions for the actor system metrics.
+pub fn describe() {
+    describe_gauge!(ACTORS, "Live actors");
+    describe_gauge!(USERS, "Users by connection state");
+    describe_gauge!(SESSIONS, "Open user connections across all devices");
+    describe_gauge!(QUEUED, "Players waiting for a quick match");
+    describe_counter!(QUICK_MATCHES, "Lobbies formed from the quick-match queue");
+    describe_gauge!(GAMES, "Actors by game phase");
+    describe_histogram!(TICK_SECONDS, Unit::Seconds, "Actor system tick duration");
+    describe_histogram!(
+        ACTION_SECONDS,
+        Unit::Seconds,
+        "Time to apply a single action"
+    );
+    describe_counter!(ACTIONS, "Actions applied");
+    describe_counter!(SERVER_MSGS, "Server messages sent to users");
+    describe_counter!(SERVER_MSG_FAILURES, "Server messages that failed to send");
+    describe_counter!(
+        SERVER_MSG_DROPS,
+        "Server messages dropped because the user's channel was full"
+    );
+    describe_counter!(
+        SERVER_MSG_COALESCED,
+        "State snapshots replaced by a newer one before the connection sent them"
+    );
+}
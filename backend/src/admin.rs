@@ -0,0 +1,140 @@
+use crate::{AppState, Config, Error};
+use actor::{Action, ActorDump, ActorId, ActorInfo, AdminReq, JoinReq, UserId};
+use axum::{
+    Json, Router,
+    extract::{Path, Request, State},
+    http::header::AUTHORIZATION,
+    middleware::{self, Next},
+    response::Response,
+    routing::{get, post},
+};
+use kanal::Sender;
+use serde::Deserialize;
+use std::sync::Arc;
+use tokio::sync::oneshot;
+
+pub const ADMIN_PATH: &str = "/admin";
+
+/// Admin routes, nested under [`ADMIN_PATH`]. Every request must carry
+/// `Authorization: Bearer <admin_token>`.
+pub fn admin_router<A: Action>(state: AppState) -> Router<AppState>
+where
+    Sender<JoinReq<A>>: axum::extract::FromRef<AppState>,
+{
+    Router::new()
+        .route("/actors", get(list_actors::<A>))
+        .route(
+            "/actors/{actor_id}",
+            get(inspect_actor::<A>).delete(close_actor::<A>),
+        )
+        .route("/actors/{actor_id}/end", post(end_actor::<A>))
+        .route("/users/{user_id}/kick", post(kick_user::<A>))
+        .route("/users/{user_id}/migrate", post(migrate_user::<A>))
+        .route_layer(middleware::from_fn_with_state(state, authorize))
+}
+
+async fn authorize(
+    State(config): State<Arc<Config>>,
+    req: Request,
+    next: Next,
+) -> Result<Response, Error> {
+    let expected = config.admin_token.as_deref().ok_or(Error::Unauthorized)?;
+    let token = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or(Error::Unauthorized)?;
+    if !constant_eq(token.as_bytes(), expected.as_bytes()) {
+        tracing::warn!("Rejected admin request with bad token");
+        return Err(Error::Unauthorized);
+    }
+    Ok(next.run(req).await)
+}
+
+fn constant_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Sends a request to the actor thread and waits for its reply.
+async fn request<A: Action, T>(
+    send_join: &Sender<JoinReq<A>>,
+    req: impl FnOnce(oneshot::Sender<T>) -> AdminReq<A>,
+) -> Result<T, Error> {
+    let (reply, recv) = oneshot::channel();
+    send_join
+        .as_async()
+        .send(JoinReq::Admin(req(reply)))
+        .await
+        .map_err(|_| Error::ActorUnavailable)?;
+    recv.await.map_err(|_| Error::ActorUnavailable)
+}
+
+fn found(found: bool) -> Result<Json<bool>, Error> {
+    found.then_some(Json(true)).ok_or(Error::NotFound)
+}
+
+#[tracing::instrument(skip(send_join))]
+async fn list_actors<A: Action>(
+    State(send_join): State<Sender<JoinReq<A>>>,
+) -> Result<Json<Vec<ActorInfo>>, Error> {
+    request(&send_join, |reply| AdminReq::List { reply })
+        .await
+        .map(Json)
+}
+
+#[tracing::instrument(skip(send_join))]
+async fn inspect_actor<A: Action>(
+    State(send_join): State<Sender<JoinReq<A>>>,
+    Path(actor_id): Path<ActorId>,
+) -> Result<Json<ActorDump<A>>, Error> {
+    request(&send_join, |reply| AdminReq::Inspect { actor_id, reply })
+        .await?
+        .map(Json)
+        .ok_or(Error::NotFound)
+}
+
+#[tracing::instrument(skip(send_join))]
+async fn end_actor<A: Action>(
+    State(send_join): State<Sender<JoinReq<A>>>,
+    Path(actor_id): Path<ActorId>,
+) -> Result<Json<bool>, Error> {
+    found(request(&send_join, |reply| AdminReq::End { actor_id, reply }).await?)
+}
+
+#[tracing::instrument(skip(send_join))]
+async fn close_actor<A: Action>(
+    State(send_join): State<Sender<JoinReq<A>>>,
+    Path(actor_id): Path<ActorId>,
+) -> Result<Json<bool>, Error> {
+    found(request(&send_join, |reply| AdminReq::Close { actor_id, reply }).await?)
+}
+
+#[tracing::instrument(skip(send_join))]
+async fn kick_user<A: Action>(
+    State(send_join): State<Sender<JoinReq<A>>>,
+    Path(user_id): Path<UserId>,
+) -> Result<Json<bool>, Error> {
+    found(request(&send_join, |reply| AdminReq::Kick { user_id, reply }).await?)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MigrateBody {
+    pub actor_id: ActorId,
+}
+
+#[tracing::instrument(skip(send_join))]
+async fn migrate_user<A: Action>(
+    State(send_join): State<Sender<JoinReq<A>>>,
+    Path(user_id): Path<UserId>,
+    Json(MigrateBody { actor_id }): Json<MigrateBody>,
+) -> Result<Json<bool>, Error> {
+    request(&send_join, |reply| AdminReq::Migrate {
+        user_id,
+        actor_id,
+        reply,
+    })
+    .await?
+    .map(|()| Json(true))
+    .map_err(Error::Migrate)
+}
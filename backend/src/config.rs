@@ -0,0 +1,166 @@
+use crate::{ConfigError, FRAME_CEILING, Limits};
+use actor::ActorConfig;
+use game::{MIN_PROTOCOL_VERSION, PROTOCOL_VERSION, ProtocolVersion, Ruleset};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Server configuration. Loaded from TOML, then overridden by environment
+/// variables and command line flags.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub bind_addr: SocketAddr,
+    /// Directory of the exported frontend.
+    pub static_dir: PathBuf,
+    pub database_path: PathBuf,
+    pub snapshot_dir: PathBuf,
+    /// Capacity of the channel carrying join requests to the actor thread.
+    pub join_bound: usize,
+    /// Messages queued per connection besides the latest table snapshot,
+    /// which a slow connection only ever holds one of.
+    pub msg_bound: usize,
+    /// Records waiting for the storage thread past which it warns that it is
+    /// falling behind. Records are never dropped.
+    pub record_bound: usize,
+    /// Seconds clients are told to wait before reconnecting after a shutdown.
+    pub reconnect_after_secs: u64,
+    /// How often the server pings each socket.
+    pub ping_interval_secs: u64,
+    /// A socket that sends nothing, not even a pong, for this long is closed
+    /// and its user disconnected.
+    pub ping_timeout_secs: u64,
+    pub actor: ActorConfig,
+    pub limits: Limits,
+    /// Default ruleset for new lobbies.
+    pub rules: Ruleset,
+    /// Bearer token for the admin API. The API is disabled when unset.
+    pub admin_token: Option<String>,
+    /// Oldest client protocol revision to accept. Raise it to turn away
+    /// stale frontend bundles after a release.
+    pub min_protocol_version: u16,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            bind_addr: SocketAddr::from(([0, 0, 0, 0], 3000)),
+            static_dir: "frontend/out".into(),
+            database_path: "regicide.db".into(),
+            snapshot_dir: "snapshots".into(),
+            join_bound: 1024,
+            msg_bound: 16,
+            record_bound: 4096,
+            reconnect_after_secs: 5,
+            ping_interval_secs: 5,
+            ping_timeout_secs: 15,
+            actor: ActorConfig::default(),
+            limits: Limits::default(),
+            rules: Ruleset::default(),
+            admin_token: None,
+            min_protocol_version: MIN_PROTOCOL_VERSION,
+        }
+    }
+}
+
+impl Config {
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let text = fs::read_to_string(path).map_err(|source| ConfigError::Read {
+            path: path.to_owned(),
+            source,
+        })?;
+        toml::from_str(&text).map_err(|source| ConfigError::Parse {
+            path: path.to_owned(),
+            source,
+        })
+    }
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let positive = [
+            ("join_bound", self.join_bound as u64),
+            ("msg_bound", self.msg_bound as u64),
+            ("record_bound", self.record_bound as u64),
+            ("ping_interval_secs", self.ping_interval_secs),
+            ("actor.tick_ms", self.actor.tick_ms),
+            ("actor.recv_bound", self.actor.recv_bound as u64),
+            ("actor.max_sessions", self.actor.max_sessions as u64),
+            (
+                "actor.snapshot_interval_secs",
+                self.actor.snapshot_interval_secs,
+            ),
+        ];
+        if let Some((field, _)) = positive.iter().find(|(_, value)| *value == 0) {
+            return Err(ConfigError::Invalid {
+                field,
+                reason: "must be greater than 0".to_string(),
+            });
+        }
+        if !(1..=FRAME_CEILING).contains(&self.limits.max_frame_bytes) {
+            return Err(ConfigError::Invalid {
+                field: "limits.max_frame_bytes",
+                reason: format!("must be between 1 and {FRAME_CEILING}"),
+            });
+        }
+        let limits = [
+            ("limits.connections", self.limits.connections),
+            ("limits.joins", self.limits.joins),
+            ("limits.actions", self.limits.actions),
+        ];
+        if let Some((field, _)) = limits
+            .iter()
+            .find(|(_, limit)| limit.burst == 0 || limit.per_sec.is_nan() || limit.per_sec <= 0.0)
+        {
+            return Err(ConfigError::Invalid {
+                field,
+                reason: "burst and per_sec must be greater than 0".to_string(),
+            });
+        }
+        if self.ping_timeout_secs <= self.ping_interval_secs {
+            return Err(ConfigError::Invalid {
+                field: "ping_timeout_secs",
+                reason: "must be greater than ping_interval_secs".to_string(),
+            });
+        }
+        if self
+            .admin_token
+            .as_ref()
+            .is_some_and(|token| token.len() < 16)
+        {
+            return Err(ConfigError::Invalid {
+                field: "admin_token",
+                reason: "must be at least 16 characters".to_string(),
+            });
+        }
+        if !(MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&self.min_protocol_version) {
+            return Err(ConfigError::Invalid {
+                field: "min_protocol_version",
+                reason: format!("must be between {MIN_PROTOCOL_VERSION} and {PROTOCOL_VERSION}"),
+            });
+        }
+        self.rules
+            .validate()
+            .map_err(|reason| ConfigError::Invalid {
+                field: "rules",
+                reason,
+            })
+    }
+    /// What the server tells clients it speaks.
+    pub fn protocol(&self) -> ProtocolVersion {
+        ProtocolVersion {
+            version: PROTOCOL_VERSION,
+            min_version: self.min_protocol_version,
+        }
+    }
+    pub fn reconnect_after(&self) -> Duration {
+        Duration::from_secs(self.reconnect_after_secs)
+    }
+    pub fn ping_interval(&self) -> Duration {
+        Duration::from_secs(self.ping_interval_secs)
+    }
+    pub fn ping_timeout(&self) -> Duration {
+        Duration::from_secs(self.ping_timeout_secs)
+    }
+}
@@ -1,18 +1,83 @@
+use actor::MigrateError;
 use axum::{
     Json,
     response::{IntoResponse, Response},
 };
 use hyper::StatusCode;
+use std::path::PathBuf;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("Error joining game")]
     JoinError,
+    #[error("Actor system unavailable")]
+    ActorUnavailable,
+    #[error("Not found")]
+    NotFound,
+    #[error("Unauthorized")]
+    Unauthorized,
+    #[error("Cannot migrate user: {0:?}")]
+    Migrate(MigrateError),
+    #[error("Invalid client token")]
+    InvalidToken,
+    #[error("Invalid quick-match preference")]
+    InvalidPreference,
+    #[error("Unexpected message")]
+    UnexpectedMessage,
+    #[error("This session may not act")]
+    Inactive,
+    #[error("Too many requests")]
+    RateLimited,
+    #[error("Message too large")]
+    TooLarge,
+    #[error("Client protocol {version} (accepting {min_version}+) is incompatible")]
+    IncompatibleVersion { version: u16, min_version: u16 },
 }
 
 impl IntoResponse for Error {
     fn into_response(self) -> Response {
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(self.to_string())).into_response()
+        let status = match self {
+            Self::JoinError => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::ActorUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            Self::NotFound => StatusCode::NOT_FOUND,
+            Self::Unauthorized => StatusCode::UNAUTHORIZED,
+            Self::Migrate(_) => StatusCode::CONFLICT,
+            Self::InvalidToken | Self::InvalidPreference | Self::UnexpectedMessage => {
+                StatusCode::BAD_REQUEST
+            }
+            Self::Inactive => StatusCode::FORBIDDEN,
+            Self::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            Self::TooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::IncompatibleVersion { .. } => StatusCode::UPGRADE_REQUIRED,
+        };
+        (status, Json(self.to_string())).into_response()
     }
 }
+
+/// A frame that could not be encoded or decoded in the socket's encoding.
+#[derive(Error, Debug)]
+pub enum WireError {
+    #[error("postcard: {0}")]
+    Postcard(#[from] postcard::Error),
+    #[error("JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("{len} bytes is over the {max} byte limit")]
+    TooLarge { len: usize, max: usize },
+}
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Failed to read config file {path}: {source}")]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Failed to parse config file {path}: {source}")]
+    Parse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    #[error("Invalid config value for `{field}`: {reason}")]
+    Invalid { field: &'static str, reason: String },
+}
@@ -0,0 +1,289 @@
+use crate::*;
+use actor::{Action, ActorId, Inbox, JoinReq, UserId};
+use axum::{
+    Json,
+    extract::{ConnectInfo, Path, Query, State},
+    http::StatusCode,
+    response::{
+        IntoResponse, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
+};
+use futures_util::stream::{self, StreamExt};
+use game::{ClientMsg, ProtocolVersion, ServerMsg};
+use kanal::Sender;
+use metrics::{counter, gauge};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    net::SocketAddr,
+    sync::{
+        Arc, Mutex, PoisonError,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+use tracing::{debug, error, info, warn};
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+pub use game::{EVENTS_PATH, EVENTS_SEND_PATH};
+
+/// Name of the first event on every stream. Its data is the session id to
+/// post `ClientMsg`s to; `ServerMsg`s follow as unnamed JSON events.
+pub const SESSION_EVENT: &str = "session";
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EventsParams {
+    /// Lobby to join; a fresh one when absent.
+    #[param(value_type = Option<String>)]
+    lobby: Option<ActorId>,
+    /// Queue for a quick match instead, with this JSON `MatchPreference`.
+    quick_match: Option<String>,
+    client_token: String,
+    /// Protocol revision, as in `ClientMsg::Hello`. Treated as 0 when absent.
+    version: Option<u16>,
+    min_version: Option<u16>,
+}
+
+/// Seats held by open event streams, by the session id handed to the client.
+/// The id is random and is all a post needs, so it is the client's secret.
+pub struct EventSessions<A: Action>(Arc<Mutex<HashMap<Uuid, EventSession<A>>>>);
+
+impl<A: Action> Clone for EventSessions<A> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<A: Action> Default for EventSessions<A> {
+    fn default() -> Self {
+        Self(Default::default())
+    }
+}
+
+impl<A: Action> EventSessions<A> {
+    fn get(&self, id: &Uuid) -> Option<EventSession<A>> {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(id)
+            .cloned()
+    }
+    fn insert(&self, id: Uuid, session: EventSession<A>) {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(id, session);
+    }
+    fn remove(&self, id: &Uuid) {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(id);
+    }
+}
+
+#[derive(Clone)]
+struct EventSession<A: Action> {
+    user_id: UserId,
+    send_action: Sender<(A, UserId)>,
+    active: Arc<AtomicBool>,
+}
+
+/// The receiving half of an event stream. Dropped when the client goes away,
+/// which unregisters the session and disconnects it like a closed socket.
+struct OpenStream<A: Action> {
+    id: Uuid,
+    user_id: UserId,
+    recv: Option<Inbox<A::Msg>>,
+    sessions: EventSessions<A>,
+    send_join: Sender<JoinReq<A>>,
+}
+
+impl<A: Action> Drop for OpenStream<A> {
+    fn drop(&mut self) {
+        // The actor tells this disconnect from a newer session's by whether
+        // our receiver is gone, so drop it first.
+        self.recv = None;
+        self.sessions.remove(&self.id);
+        gauge!(SSE_CONNECTIONS).decrement(1);
+        let user_id = self.user_id;
+        debug!(%user_id, session = %self.id, "Event stream closed, sending disconnect");
+        // Drop cannot wait for room in the join channel without blocking a
+        // runtime thread, so the disconnect goes out from its own task.
+        let send_join = self.send_join.clone_async();
+        tokio::spawn(async move {
+            if let Err(err) = send_join.send(JoinReq::Disconnect { user_id }).await {
+                counter!(WS_SEND_FAILURES, "target" => "join").increment(1);
+                error!(%user_id, error = %err, "Failed to send disconnect");
+            }
+        });
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = EVENTS_PATH,
+    params(EventsParams),
+    responses(
+        (status = 200, description = "A `session` event carrying the id to post to, then each \
+            ServerMsg as a JSON event", body = ServerMsg, content_type = "text/event-stream"),
+        (status = 400, description = "Invalid client token or quick-match preference", body = String),
+        (status = 429, description = "Too many connections or joins", body = String),
+    )
+)]
+pub async fn events_handler<A: Action>(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<EventsParams>,
+    State(send_join): State<Sender<JoinReq<A>>>,
+    State(sessions): State<EventSessions<A>>,
+    State(config): State<Arc<Config>>,
+    State(limiters): State<Arc<Limiters>>,
+) -> Result<Response, Error> {
+    let ip = addr.ip();
+    if !limiters.connections.check(ip) {
+        counter!(WS_REJECTED, "reason" => "rate").increment(1);
+        warn!(%ip, "Too many connections, refusing event stream");
+        return Err(Error::RateLimited);
+    }
+    let user_id = UserId::from(&params.client_token).map_err(|_| Error::InvalidToken)?;
+    let destination = match &params.quick_match {
+        Some(preference) => Destination::QuickMatch(
+            serde_json::from_str(preference).map_err(|_| Error::InvalidPreference)?,
+        ),
+        None => Destination::Lobby(params.lobby),
+    };
+    let protocol = config.protocol();
+    let peer = params
+        .version
+        .map_or(ProtocolVersion::LEGACY, |version| ProtocolVersion {
+            version,
+            min_version: params.min_version.unwrap_or_default(),
+        });
+    if !protocol.compatible(peer) {
+        counter!(WS_REJECTED, "reason" => "version").increment(1);
+        info!(%user_id, version = peer.version, "Rejected incompatible event stream");
+        let msg = A::incompatible_msg(protocol.version, protocol.min_version);
+        return Ok(Sse::new(stream::iter(event(&msg))).into_response());
+    }
+    if !limiters.connection(user_id) {
+        counter!(WS_REJECTED, "reason" => "rate").increment(1);
+        warn!(%ip, %user_id, "Too many connections, refusing event stream");
+        return Err(Error::RateLimited);
+    }
+    if !limiters.join(ip, user_id) {
+        counter!(WS_REJECTED, "reason" => "rate").increment(1);
+        warn!(%ip, %user_id, "Too many joins, refusing event stream");
+        return Err(Error::RateLimited);
+    }
+    let seat = connect(&send_join, user_id, destination, config.msg_bound).await?;
+    let id = Uuid::new_v4();
+    sessions.insert(
+        id,
+        EventSession {
+            user_id,
+            send_action: seat.send_action,
+            active: seat.active,
+        },
+    );
+    gauge!(SSE_CONNECTIONS).increment(1);
+    debug!(%user_id, session = %id, "Event stream opened");
+    let open = OpenStream {
+        id,
+        user_id,
+        recv: Some(seat.recv),
+        sessions,
+        send_join,
+    };
+    let version = peer.version;
+    let msgs = stream::unfold(open, |open| async move {
+        let msg = open.recv.as_ref()?.recv().await?;
+        Some((msg, open))
+    })
+    .filter_map(move |msg| async move { event(&A::downgrade(msg, version)) });
+    let first = Event::default().event(SESSION_EVENT).data(id.to_string());
+    let stream = stream::once(async move { Ok(first) }).chain(msgs);
+    let keep_alive = KeepAlive::new().interval(config.ping_interval());
+    Ok(Sse::new(stream).keep_alive(keep_alive).into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = EVENTS_SEND_PATH,
+    params(("session" = String, Path, description = "Id from the stream's `session` event")),
+    request_body(content = String, description = "A ClientMsg as JSON, `Action` or `RequestHint`",
+        content_type = "application/json"),
+    responses(
+        (status = 202, description = "Handed to the game"),
+        (status = 400, description = "Not an action or hint request", body = String),
+        (status = 403, description = "The session policy lets another session act", body = String),
+        (status = 404, description = "No open stream with this id", body = String),
+        (status = 413, description = "Body larger than `limits.max_frame_bytes`", body = String),
+        (status = 429, description = "Too many actions", body = String),
+    )
+)]
+pub async fn post_event<A: Action>(
+    Path(session): Path<Uuid>,
+    State(send_join): State<Sender<JoinReq<A>>>,
+    State(sessions): State<EventSessions<A>>,
+    State(limiters): State<Arc<Limiters>>,
+    Json(msg): Json<ClientMsg<A>>,
+) -> Result<StatusCode, Error> {
+    let EventSession {
+        user_id,
+        send_action,
+        active,
+    } = sessions.get(&session).ok_or(Error::NotFound)?;
+    if !limiters.action(user_id) {
+        counter!(WS_REJECTED, "reason" => "rate").increment(1);
+        debug!(%user_id, "Too many actions from event session");
+        return Err(Error::RateLimited);
+    }
+    match msg {
+        ClientMsg::Action { .. } if !active.load(Ordering::Relaxed) => {
+            counter!(WS_REJECTED, "reason" => "inactive").increment(1);
+            Err(Error::Inactive)
+        }
+        ClientMsg::Action { action } => {
+            debug!(%user_id, "Received action from event session");
+            send_action
+                .as_async()
+                .send((action, user_id))
+                .await
+                .map_err(|err| {
+                    counter!(WS_SEND_FAILURES, "target" => "actor").increment(1);
+                    error!(%user_id, error = %err, "Failed to send action to actor");
+                    Error::ActorUnavailable
+                })?;
+            Ok(StatusCode::ACCEPTED)
+        }
+        ClientMsg::RequestHint => {
+            send_join
+                .as_async()
+                .send(JoinReq::Hint { user_id })
+                .await
+                .map_err(|err| {
+                    counter!(WS_SEND_FAILURES, "target" => "join").increment(1);
+                    error!(%user_id, error = %err, "Failed to send hint request");
+                    Error::ActorUnavailable
+                })?;
+            Ok(StatusCode::ACCEPTED)
+        }
+        _ => {
+            counter!(WS_REJECTED, "reason" => "unexpected").increment(1);
+            Err(Error::UnexpectedMessage)
+        }
+    }
+}
+
+fn event<T: Serialize>(msg: &T) -> Option<Result<Event, Infallible>> {
+    match Event::default().json_data(msg) {
+        Ok(event) => Some(Ok(event)),
+        Err(err) => {
+            error!(error = %err, "Failed to serialize server message");
+            None
+        }
+    }
+}
@@ -1,7 +1,14 @@
-use actor::{ActorList, JoinReq, actor_loop};
-use axum::{Router, extract::FromRef, routing::get};
-use game::RegicideAction;
+use actor::{ActorList, JoinReq, SnapshotDir, actor_loop};
+use axum::{
+    Router,
+    extract::{DefaultBodyLimit, FromRef},
+    routing::{get, post},
+};
+use game::{Lobby, RegicideAction};
 use kanal::Sender;
+use metrics_exporter_prometheus::PrometheusHandle;
+use std::{net::SocketAddr, sync::Arc};
+use storage::{SqliteStorage, record_loop};
 use tower_http::{
     compression::CompressionLayer,
     services::{ServeDir, ServeFile},
@@ -10,66 +17,158 @@ use tower_http::{
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
+mod admin;
+mod config;
 mod error;
+mod events;
+mod limit;
 mod list;
+mod seat;
+mod shutdown;
+mod telemetry;
 mod tracing_setup;
 mod ws;
 
+pub use admin::*;
+pub use config::*;
 pub use error::*;
+pub use events::*;
+pub use limit::*;
 pub use list::*;
+pub use seat::*;
+pub use shutdown::*;
+pub use telemetry::*;
 pub use ws::*;
 
-const JOIN_BOUND: usize = 1024;
-
 #[derive(Clone, FromRef)]
 pub struct AppState {
     send_join: Sender<JoinReq<RegicideAction>>,
-    actor_list: ActorList,
+    events: EventSessions<RegicideAction>,
+    limiters: Arc<Limiters>,
+    actor_list: ActorList<Lobby>,
+    config: Arc<Config>,
+    metrics: PrometheusHandle,
 }
 
 #[derive(OpenApi)]
-#[openapi(paths(ws_handler))]
+#[openapi(paths(
+    ws_handler,
+    events_handler,
+    post_event,
+    get_actor_list,
+    lobby_events,
+    get_metrics
+))]
 pub struct ApiDoc;
 
-pub async fn serve() {
+pub async fn serve(config: Config) {
     tracing_setup::init_tracing();
+    let metrics = init_metrics();
 
-    let dir = "frontend/out";
-    let bind_addr = "0.0.0.0:3000";
+    let dir = &config.static_dir;
+    let bind_addr = config.bind_addr;
 
     tracing::info!(
-        dir = %dir,
+        dir = %dir.display(),
         bind_addr = %bind_addr,
+        db_path = %config.database_path.display(),
+        snapshot_dir = %config.snapshot_dir.display(),
+        rules = ?config.rules,
         "Starting Regicide server"
     );
+    if !dir.is_dir() {
+        tracing::warn!(dir = %dir.display(), "Static directory does not exist");
+    }
 
-    let static_service =
-        ServeDir::new(dir).not_found_service(ServeFile::new(format!("{dir}/404.html")));
+    let static_service = ServeDir::new(dir).not_found_service(ServeFile::new(dir.join("404.html")));
 
     let actor_list = ActorList::default();
-    let (send_join, recv_join) = kanal::bounded(JOIN_BOUND);
-    let state = AppState {
-        send_join,
-        actor_list: actor_list.clone(),
-    };
+    let (send_join, recv_join) = kanal::bounded(config.join_bound);
+
+    let storage = SqliteStorage::open(&config.database_path).expect("Failed to open database");
+    let snapshots =
+        SnapshotDir::new(&config.snapshot_dir).expect("Failed to create snapshot directory");
+    let (send_record, recv_record) = kanal::unbounded();
+    let record_backlog = config.record_bound;
+    tracing::info!("Spawning record thread");
+    let record_handle = std::thread::spawn(move || {
+        record_loop::<RegicideAction, _>(recv_record, storage, record_backlog);
+    });
 
     tracing::info!("Spawning actor system thread");
-    std::thread::spawn(move || {
-        actor_loop(recv_join, actor_list);
+    let (actor_config, rules) = (config.actor.clone(), config.rules);
+    let actor_list_handle = actor_list.clone();
+    let actor_handle = std::thread::spawn(move || {
+        actor_loop::<RegicideAction>(
+            recv_join,
+            actor_list_handle,
+            send_record,
+            snapshots,
+            actor_config,
+            rules,
+        );
     });
 
-    let app = Router::new()
+    let reconnect_after = config.reconnect_after();
+    let state = AppState {
+        send_join: send_join.clone(),
+        events: EventSessions::default(),
+        limiters: Arc::new(Limiters::new(&config.limits)),
+        actor_list,
+        config: Arc::new(config),
+        metrics,
+    };
+
+    let admin = state.config.admin_token.is_some();
+    let mut app = Router::new()
         .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()))
         .route(WS_HANDLER_PATH, get(ws_handler::<RegicideAction>))
+        .route(EVENTS_PATH, get(events_handler::<RegicideAction>))
+        .route(
+            EVENTS_SEND_PATH,
+            post(post_event::<RegicideAction>)
+                .layer(DefaultBodyLimit::max(state.config.limits.max_frame_bytes)),
+        )
         .route(ACTOR_LIST_PATH, get(get_actor_list))
+        .route(LOBBY_EVENTS_PATH, get(lobby_events))
+        .route(METRICS_PATH, get(get_metrics));
+    if admin {
+        app = app.nest(ADMIN_PATH, admin_router::<RegicideAction>(state.clone()));
+    } else {
+        tracing::info!("No admin token configured, admin API disabled");
+    }
+    let app = app
         .fallback_service(static_service)
         .with_state(state)
         .layer(CompressionLayer::new())
         .layer(TraceLayer::new_for_http());
 
     tracing::info!("Binding to {}", bind_addr);
-    let listener = tokio::net::TcpListener::bind(bind_addr).await.unwrap();
+    let listener = tokio::net::TcpListener::bind(bind_addr)
+        .await
+        .expect("Failed to bind");
 
     tracing::info!("Server ready, listening on {}", bind_addr);
-    axum::serve(listener, app).await.unwrap();
+    let app = app.into_make_service_with_connect_info::<SocketAddr>();
+    if let Err(err) = axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_signal(send_join, reconnect_after))
+        .await
+    {
+        tracing::error!(error = %err, "Server error");
+    }
+
+    tracing::info!("Waiting for actor and record threads");
+    let joined = tokio::task::spawn_blocking(move || {
+        if actor_handle.join().is_err() {
+            tracing::error!("Actor thread panicked");
+        }
+        if record_handle.join().is_err() {
+            tracing::error!("Record thread panicked");
+        }
+    })
+    .await;
+    if let Err(err) = joined {
+        tracing::error!(error = %err, "Failed to join background threads");
+    }
+    tracing::info!("Server stopped");
 }
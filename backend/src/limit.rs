@@ -0,0 +1,227 @@
+use actor::UserId;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    net::IpAddr,
+    sync::{Mutex, PoisonError},
+    time::Instant,
+};
+
+/// Largest `max_frame_bytes` allowed. Sockets refuse anything past it
+/// before buffering it at all.
+pub const FRAME_CEILING: usize = 1 << 20;
+/// Buckets kept before idle ones are first swept out.
+const PRUNE_AT: usize = 4096;
+
+/// A token bucket: up to `burst` requests at once, refilled at `per_sec`.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimit {
+    pub burst: u32,
+    pub per_sec: f64,
+}
+
+/// Abuse protection for client connections.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Limits {
+    /// Largest client message accepted, in bytes. Bigger ones close the
+    /// connection before they are decoded.
+    pub max_frame_bytes: usize,
+    /// New WebSocket or event stream connections per client IP, and
+    /// separately per client token once it asks for a seat.
+    pub connections: RateLimit,
+    /// Join requests per client IP, and separately per client token.
+    pub joins: RateLimit,
+    /// Actions and hint requests per client token. Players behind one shared
+    /// address each get their own.
+    pub actions: RateLimit,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_frame_bytes: 4096,
+            connections: RateLimit {
+                burst: 100,
+                per_sec: 20.0,
+            },
+            joins: RateLimit {
+                burst: 50,
+                per_sec: 10.0,
+            },
+            actions: RateLimit {
+                burst: 30,
+                per_sec: 10.0,
+            },
+        }
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    last: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, limit: RateLimit, now: Instant) {
+        let earned = (now - self.last).as_secs_f64() * limit.per_sec;
+        self.tokens = (self.tokens + earned).min(limit.burst as f64);
+        self.last = now;
+    }
+}
+
+/// Token buckets for one kind of request, keyed by who is asking.
+pub struct RateLimiter<K> {
+    limit: RateLimit,
+    buckets: Mutex<Buckets<K>>,
+}
+
+struct Buckets<K> {
+    by_key: HashMap<K, Bucket>,
+    /// Size at which to sweep next, doubled past what a sweep leaves so a
+    /// flood of live keys cannot make every check pay for one.
+    prune_at: usize,
+}
+
+impl<K: Eq + Hash> RateLimiter<K> {
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            buckets: Mutex::new(Buckets {
+                by_key: HashMap::new(),
+                prune_at: PRUNE_AT,
+            }),
+        }
+    }
+    /// Spends one request for `key`. False when it has none left.
+    pub fn check(&self, key: K) -> bool {
+        let now = Instant::now();
+        let limit = self.limit;
+        let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+        if buckets.by_key.len() >= buckets.prune_at {
+            // A full bucket behaves exactly like a missing one.
+            buckets.by_key.retain(|_, bucket| {
+                bucket.refill(limit, now);
+                bucket.tokens < limit.burst as f64
+            });
+            buckets.prune_at = (buckets.by_key.len() * 2).max(PRUNE_AT);
+        }
+        let bucket = buckets.by_key.entry(key).or_insert(Bucket {
+            tokens: limit.burst as f64,
+            last: now,
+        });
+        bucket.refill(limit, now);
+        if bucket.tokens < 1.0 {
+            return false;
+        }
+        bucket.tokens -= 1.0;
+        true
+    }
+}
+
+/// The rate limiters for every transport, built from [`Limits`].
+pub struct Limiters {
+    pub max_frame_bytes: usize,
+    pub connections: RateLimiter<IpAddr>,
+    connection_users: RateLimiter<UserId>,
+    join_ips: RateLimiter<IpAddr>,
+    join_users: RateLimiter<UserId>,
+    action_users: RateLimiter<UserId>,
+}
+
+impl Limiters {
+    pub fn new(limits: &Limits) -> Self {
+        Self {
+            max_frame_bytes: limits.max_frame_bytes,
+            connections: RateLimiter::new(limits.connections),
+            connection_users: RateLimiter::new(limits.connections),
+            join_ips: RateLimiter::new(limits.joins),
+            join_users: RateLimiter::new(limits.joins),
+            action_users: RateLimiter::new(limits.actions),
+        }
+    }
+    /// Spends a connection for the token a new connection first asks with.
+    /// Its address was charged when it opened, before any token was known.
+    pub fn connection(&self, user_id: UserId) -> bool {
+        self.connection_users.check(user_id)
+    }
+    /// Spends a join for both the address and the token asking.
+    pub fn join(&self, ip: IpAddr, user_id: UserId) -> bool {
+        // Both are charged, so one cannot dodge the limit by switching the
+        // other.
+        self.join_ips.check(ip) & self.join_users.check(user_id)
+    }
+    /// Spends an action or hint request for the token sending it. Only the
+    /// handshake is limited by address: a seated player is known by token,
+    /// and a busy table behind one NAT would otherwise share a bucket.
+    pub fn action(&self, user_id: UserId) -> bool {
+        self.action_users.check(user_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    fn limiters(burst: u32) -> Limiters {
+        let limit = RateLimit {
+            burst,
+            per_sec: 0.0,
+        };
+        Limiters::new(&Limits {
+            max_frame_bytes: 64,
+            connections: limit,
+            joins: limit,
+            actions: limit,
+        })
+    }
+
+    fn user(name: &str) -> UserId {
+        UserId::from(name).unwrap()
+    }
+
+    #[test]
+    fn actions_count_per_token() {
+        let limiters = limiters(2);
+        let ann = user("ann");
+        assert!(limiters.action(ann));
+        assert!(limiters.action(ann));
+        assert!(!limiters.action(ann));
+        // A tablemate on the same address is not held back.
+        assert!(limiters.action(user("bob")));
+    }
+
+    #[test]
+    fn joins_count_per_address_across_tokens() {
+        let limiters = limiters(2);
+        let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        assert!(limiters.join(ip, user("ann")));
+        assert!(limiters.join(ip, user("bob")));
+        assert!(!limiters.join(ip, user("cat")));
+        let other = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        assert!(limiters.join(other, user("dan")));
+    }
+
+    #[test]
+    fn connections_count_per_token() {
+        let limiters = limiters(1);
+        assert!(limiters.connection(user("ann")));
+        assert!(!limiters.connection(user("ann")));
+        assert!(limiters.connection(user("bob")));
+    }
+
+    #[test]
+    fn buckets_refill_over_time() {
+        let limiter = RateLimiter::new(RateLimit {
+            burst: 1,
+            per_sec: 1000.0,
+        });
+        assert!(limiter.check(1));
+        assert!(!limiter.check(1));
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        assert!(limiter.check(1));
+    }
+}
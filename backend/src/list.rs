@@ -1,8 +1,23 @@
-use crate::Error;
-use actor::ActorList;
-use axum::{Json, extract::State, response::IntoResponse};
+use crate::*;
+use actor::{ActorId, ActorList, LobbyEvent};
+use axum::{
+    Json,
+    extract::{ConnectInfo, Query, State},
+    response::{
+        IntoResponse,
+        sse::{Event, KeepAlive, Sse},
+    },
+};
+use futures_util::stream::{self, StreamExt};
+use game::Lobby;
+use metrics::counter;
+use serde::Deserialize;
+use std::{collections::HashSet, convert::Infallible, net::SocketAddr, sync::Arc};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{debug, error, warn};
+use utoipa::IntoParams;
 
-pub const ACTOR_LIST_PATH: &str = "/lobbies";
+pub use game::{ACTOR_LIST_PATH, LOBBY_EVENTS_PATH};
 
 #[utoipa::path(
     get,
@@ -15,12 +30,154 @@ pub const ACTOR_LIST_PATH: &str = "/lobbies";
 )]
 #[tracing::instrument(skip(actor_list))]
 pub async fn get_actor_list(
-    State(actor_list): State<ActorList>,
+    State(actor_list): State<ActorList<Lobby>>,
 ) -> Result<impl IntoResponse, Error> {
-    let res = actor_list.read().await;
+    let res: Vec<(ActorId, u8)> = actor_list
+        .read()
+        .await
+        .into_iter()
+        .map(|(actor_id, lobby)| (actor_id, lobby.players))
+        .collect();
     let lobby_count = res.len();
 
     tracing::debug!(lobby_count, "Returning lobby list");
 
     Ok(Json(res))
 }
+
+/// Which lobbies a subscriber wants to hear about. Unset fields match any.
+#[derive(Clone, Copy, Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LobbyFilter {
+    /// Seats still free, at least.
+    open_seats: Option<u8>,
+    /// Table size.
+    max_players: Option<u8>,
+    /// Jesters house rule; lobbies following the player count never match.
+    jesters: Option<u8>,
+}
+
+impl LobbyFilter {
+    pub fn matches(&self, lobby: &Lobby) -> bool {
+        self.open_seats.is_none_or(|n| lobby.open_seats() >= n)
+            && self.max_players.is_none_or(|n| lobby.max_players == n)
+            && self.jesters.is_none_or(|n| lobby.jesters == Some(n))
+    }
+}
+
+/// One subscriber's view of the list: the events it is sent are relative to
+/// the lobbies it has already been shown, so a lobby that stops matching the
+/// filter closes for it.
+struct Subscriber {
+    filter: LobbyFilter,
+    shown: HashSet<ActorId>,
+    recv: broadcast::Receiver<LobbyEvent<Lobby>>,
+    actor_list: ActorList<Lobby>,
+}
+
+impl Subscriber {
+    fn apply(&mut self, event: LobbyEvent<Lobby>) -> Option<LobbyEvent<Lobby>> {
+        match event {
+            LobbyEvent::Created { actor_id, lobby } | LobbyEvent::Updated { actor_id, lobby } => {
+                if !self.filter.matches(&lobby) {
+                    return self
+                        .shown
+                        .remove(&actor_id)
+                        .then_some(LobbyEvent::Closed { actor_id });
+                }
+                Some(match self.shown.insert(actor_id) {
+                    true => LobbyEvent::Created { actor_id, lobby },
+                    false => LobbyEvent::Updated { actor_id, lobby },
+                })
+            }
+            LobbyEvent::Closed { actor_id } => self
+                .shown
+                .remove(&actor_id)
+                .then_some(LobbyEvent::Closed { actor_id }),
+        }
+    }
+    /// Brings the subscriber up to `list`, closing what it no longer holds.
+    fn resync(&mut self, list: Vec<(ActorId, Lobby)>) -> Vec<LobbyEvent<Lobby>> {
+        let listed: HashSet<_> = list.iter().map(|(actor_id, _)| *actor_id).collect();
+        let gone: Vec<_> = self.shown.difference(&listed).copied().collect();
+        let mut events: Vec<_> = gone
+            .into_iter()
+            .filter_map(|actor_id| self.apply(LobbyEvent::Closed { actor_id }))
+            .collect();
+        events.extend(
+            list.into_iter().filter_map(|(actor_id, lobby)| {
+                self.apply(LobbyEvent::Updated { actor_id, lobby })
+            }),
+        );
+        events
+    }
+    async fn next(&mut self) -> Option<Vec<LobbyEvent<Lobby>>> {
+        match self.recv.recv().await {
+            Ok(event) => Some(self.apply(event).into_iter().collect()),
+            Err(RecvError::Lagged(skipped)) => {
+                debug!(skipped, "Lobby subscriber fell behind, resyncing");
+                let list = self.actor_list.read().await;
+                Some(self.resync(list))
+            }
+            Err(RecvError::Closed) => None,
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = LOBBY_EVENTS_PATH,
+    params(LobbyFilter),
+    responses(
+        (status = 200, description = "A `Created` event for each open lobby matching the filter, \
+            then every change as a JSON LobbyEvent", body = Lobby, content_type = "text/event-stream"),
+        (status = 429, description = "Too many connections from this address", body = String),
+    )
+)]
+pub async fn lobby_events(
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(filter): Query<LobbyFilter>,
+    State(actor_list): State<ActorList<Lobby>>,
+    State(config): State<Arc<Config>>,
+    State(limiters): State<Arc<Limiters>>,
+) -> Result<impl IntoResponse, Error> {
+    let ip = addr.ip();
+    if !limiters.connections.check(ip) {
+        counter!(WS_REJECTED, "reason" => "rate").increment(1);
+        warn!(%ip, "Too many connections, refusing lobby subscription");
+        return Err(Error::RateLimited);
+    }
+    let (list, recv) = actor_list.subscribe().await;
+    let mut subscriber = Subscriber {
+        filter,
+        shown: HashSet::new(),
+        recv,
+        actor_list,
+    };
+    let first = subscriber.resync(list);
+    debug!(
+        ?filter,
+        lobby_count = first.len(),
+        "Lobby subscription opened"
+    );
+    let changes = stream::unfold(subscriber, |mut subscriber| async move {
+        let events = subscriber.next().await?;
+        Some((stream::iter(events), subscriber))
+    })
+    .flatten();
+    let stream = stream::iter(first)
+        .chain(changes)
+        .filter_map(|event| async move { lobby_event(&event) });
+    let keep_alive = KeepAlive::new().interval(config.ping_interval());
+    Ok(Sse::new(stream).keep_alive(keep_alive))
+}
+
+fn lobby_event(event: &LobbyEvent<Lobby>) -> Option<Result<Event, Infallible>> {
+    match Event::default().json_data(event) {
+        Ok(event) => Some(Ok(event)),
+        Err(err) => {
+            error!(error = %err, "Failed to serialize lobby event");
+            None
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use crate::*;
+use actor::{Action, ActorId, Inbox, JoinReq, UserId, mailbox};
+use game::ClientMsg;
+use kanal::Sender;
+use metrics::counter;
+use std::sync::{Arc, atomic::AtomicBool};
+use tracing::{error, info};
+
+/// A user seated through `JoinReq::Connect`, as seen by the transport that
+/// asked for it.
+pub struct Seat<A: Action> {
+    pub user_id: UserId,
+    pub send_action: Sender<(A, UserId)>,
+    pub recv: Inbox<A::Msg>,
+    /// Cleared while the session policy only lets another session act.
+    pub active: Arc<AtomicBool>,
+}
+
+/// Where a transport asks to seat its user.
+pub enum Destination<A: Action> {
+    /// This lobby, or a fresh one when `None`.
+    Lobby(Option<ActorId>),
+    /// Wherever the quick-match queue puts them.
+    QuickMatch(A::Preference),
+}
+
+impl<A: Action> Destination<A> {
+    /// The user and destination of a `Join` or `QuickMatch`, or any other
+    /// message back.
+    pub fn requested(msg: ClientMsg<A>) -> Result<(UserId, Self), ClientMsg<A>> {
+        match msg {
+            ClientMsg::Join {
+                client_token,
+                lobby,
+            } => Ok((client_token, Self::Lobby(lobby))),
+            ClientMsg::QuickMatch {
+                client_token,
+                preference,
+            } => Ok((client_token, Self::QuickMatch(preference))),
+            other => Err(other),
+        }
+    }
+}
+
+/// Seats `user_id` at `destination`. Every transport goes through here, so
+/// the actor system never knows which one a user is on.
+pub async fn connect<A: Action>(
+    send_join: &Sender<JoinReq<A>>,
+    user_id: UserId,
+    destination: Destination<A>,
+    msg_bound: usize,
+) -> Result<Seat<A>, Error> {
+    let (send_server_msg, recv_server_msg) = mailbox(msg_bound);
+    let (send_sender, recv_sender) = kanal::bounded(msg_bound);
+    let active = Arc::new(AtomicBool::new(true));
+    let join_req = match destination {
+        Destination::Lobby(lobby) => {
+            info!(%user_id, lobby = ?lobby, "Client join request");
+            JoinReq::<A>::Connect {
+                user_id,
+                actor_id: lobby,
+                send_server_msg,
+                send_sender,
+                active: active.clone(),
+            }
+        }
+        Destination::QuickMatch(preference) => {
+            info!(%user_id, ?preference, "Client quick match request");
+            JoinReq::<A>::QuickMatch {
+                user_id,
+                preference,
+                send_server_msg,
+                send_sender,
+                active: active.clone(),
+            }
+        }
+    };
+    if let Err(err) = send_join.as_async().send(join_req).await {
+        counter!(WS_SEND_FAILURES, "target" => "join").increment(1);
+        error!(%user_id, error = %err, "Failed to send join request");
+        return Err(Error::ActorUnavailable);
+    }
+    match recv_sender.to_async().recv().await {
+        Ok(send_action) => Ok(Seat {
+            user_id,
+            send_action,
+            recv: recv_server_msg,
+            active,
+        }),
+        Err(err) => {
+            counter!(WS_SEND_FAILURES, "target" => "join").increment(1);
+            error!(%user_id, error = %err, "Failed to receive action sender");
+            Err(Error::JoinError)
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use actor::{Action, JoinReq};
+use kanal::Sender;
+use std::time::Duration;
+use tokio::signal;
+use tracing::{error, info};
+
+/// Resolves on ctrl-c or SIGTERM after asking the actor system to shut down.
+pub async fn shutdown_signal<A: Action>(send_join: Sender<JoinReq<A>>, reconnect_after: Duration) {
+    let ctrl_c = async {
+        if let Err(err) = signal::ctrl_c().await {
+            error!(error = %err, "Failed to listen for ctrl-c");
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+            Ok(mut sig) => {
+                sig.recv().await;
+            }
+            Err(err) => {
+                error!(error = %err, "Failed to listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("Received ctrl-c"),
+        _ = terminate => info!("Received SIGTERM"),
+    }
+
+    info!("Shutting down, notifying actor system");
+    if let Err(err) = send_join
+        .as_async()
+        .send(JoinReq::Shutdown { reconnect_after })
+        .await
+    {
+        error!(error = %err, "Failed to send shutdown to actor system");
+    }
+}
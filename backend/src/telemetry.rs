@@ -0,0 +1,62 @@
+use axum::{extract::State, response::IntoResponse};
+use metrics::{Unit, describe_counter, describe_gauge, describe_histogram};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+
+pub const METRICS_PATH: &str = "/metrics";
+
+pub const WS_CONNECTIONS: &str = "regicide_ws_connections";
+pub const WS_REJECTED: &str = "regicide_ws_rejected_total";
+pub const WS_SEND_FAILURES: &str = "regicide_ws_send_failures_total";
+pub const SSE_CONNECTIONS: &str = "regicide_sse_connections";
+pub const WS_TIMEOUTS: &str = "regicide_ws_timeouts_total";
+pub const WS_RTT_SECONDS: &str = "regicide_ws_rtt_seconds";
+
+const SECONDS_BUCKETS: &[f64] = &[
+    0.00001, 0.00005, 0.0001, 0.0005, 0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.5,
+];
+/// Network round trips are far slower than anything measured in-process.
+const RTT_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0];
+
+/// Installs the global Prometheus recorder.
+pub fn init_metrics() -> PrometheusHandle {
+    let handle = PrometheusBuilder::new()
+        .set_buckets_for_metric(Matcher::Full(WS_RTT_SECONDS.to_string()), RTT_BUCKETS)
+        .expect("Failed to set histogram buckets")
+        .set_buckets_for_metric(Matcher::Suffix("_seconds".to_string()), SECONDS_BUCKETS)
+        .expect("Failed to set histogram buckets")
+        .install_recorder()
+        .expect("Failed to install metrics recorder");
+    actor::telemetry::describe();
+    describe_gauge!(WS_CONNECTIONS, Unit::Count, "Open WebSocket connections");
+    describe_gauge!(
+        SSE_CONNECTIONS,
+        Unit::Count,
+        "Open server-sent event streams"
+    );
+    describe_counter!(WS_REJECTED, "Client messages rejected by reason");
+    describe_counter!(
+        WS_SEND_FAILURES,
+        "Failed sends from WebSocket handlers by target"
+    );
+    describe_counter!(
+        WS_TIMEOUTS,
+        "WebSocket connections closed for missing heartbeats"
+    );
+    describe_histogram!(
+        WS_RTT_SECONDS,
+        Unit::Seconds,
+        "Heartbeat round-trip time per ping"
+    );
+    handle
+}
+
+#[utoipa::path(
+    get,
+    path = METRICS_PATH,
+    responses(
+        (status = 200, description = "Prometheus text exposition", body = String),
+    )
+)]
+pub async fn get_metrics(State(handle): State<PrometheusHandle>) -> impl IntoResponse {
+    handle.render()
+}
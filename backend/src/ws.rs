@@ -1,44 +1,125 @@
 use crate::*;
-use actor::{Action, UserId};
+use actor::{Action, Inbox, UserId, mailbox};
 use axum::{
     extract::{
-        ws::{Message, WebSocket, WebSocketUpgrade},
-        State,
+        ConnectInfo, Query, State,
+        ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade, close_code},
     },
-    response::IntoResponse,
+    response::{IntoResponse, Response},
 };
 use futures_util::{
     sink::SinkExt,
     stream::{SplitSink, SplitStream, StreamExt},
 };
-use game::{ClientMsg, ServerMsg};
-use kanal::{AsyncReceiver, Sender};
-use tokio::sync::oneshot;
+use game::{ClientMsg, Encoding, ProtocolVersion, ServerMsg};
+use kanal::Sender;
+use metrics::{counter, gauge, histogram};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, atomic::Ordering},
+    time::{Duration, Instant},
+};
+use tokio::{
+    sync::oneshot,
+    time::{MissedTickBehavior, interval, timeout},
+};
 use tracing::{debug, error, info, warn};
+use utoipa::IntoParams;
 
-pub const WS_HANDLER_PATH: &str = "/ws";
+pub use game::WS_HANDLER_PATH;
 
-const MSG_BOUND: usize = 16;
+/// How long the writer gets to send its close frame once the reader is done.
+const CLOSE_GRACE: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct WsParams {
+    /// Frame encoding, used when no subprotocol is requested. Defaults to postcard.
+    encoding: Option<Encoding>,
+}
 
 #[utoipa::path(
     get,
     path = WS_HANDLER_PATH,
-    params(),
+    params(WsParams),
     responses(
-        (status = 101, description = "A WS stream of Message values", body = ServerMsg),
+        (status = 101, description = "A WS stream of Message values, as postcard binary frames or \
+            JSON text frames depending on the `regicide.postcard`/`regicide.json` subprotocol or \
+            `encoding` parameter", body = ServerMsg),
+        (status = 429, description = "Too many connections from this address", body = String),
     )
 )]
 pub async fn ws_handler<A: Action>(
     ws: WebSocketUpgrade,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(params): Query<WsParams>,
     State(send_join): State<Sender<JoinReq<A>>>,
-) -> impl IntoResponse {
+    State(config): State<Arc<Config>>,
+    State(limiters): State<Arc<Limiters>>,
+) -> Response {
+    let ip = addr.ip();
+    if !limiters.connections.check(ip) {
+        counter!(WS_REJECTED, "reason" => "rate").increment(1);
+        warn!(%ip, "Too many connections, refusing upgrade");
+        return Error::RateLimited.into_response();
+    }
+    let msg_bound = config.msg_bound;
+    let protocol = config.protocol();
+    let ws = ws
+        .protocols(Encoding::ALL.map(Encoding::protocol))
+        .max_frame_size(FRAME_CEILING)
+        .max_message_size(FRAME_CEILING);
+    let encoding = ws
+        .selected_protocol()
+        .and_then(|protocol| protocol.to_str().ok())
+        .and_then(Encoding::from_protocol)
+        .or(params.encoding)
+        .unwrap_or_default();
+    let (interval, timeout) = (config.ping_interval(), config.ping_timeout());
     ws.on_upgrade(async move |socket| {
-        debug!("WebSocket connection opened");
+        debug!(?encoding, %ip, "WebSocket connection opened");
+        gauge!(WS_CONNECTIONS).increment(1);
+        let heartbeat = Heartbeat::new(interval, timeout);
+        let conn = Conn {
+            ip,
+            encoding,
+            protocol,
+            heartbeat,
+            msg_bound,
+            limiters,
+        };
         let (send_receiver, recv_receiver) = oneshot::channel();
+        let (send_close, recv_close) = oneshot::channel();
         let (sender, receiver) = socket.split();
-        let read_handle = tokio::spawn(read::<A>(receiver, send_join.clone(), send_receiver));
-        let write_handle = tokio::spawn(write::<A>(sender, recv_receiver));
-        let (write_res, read_res) = tokio::join!(write_handle, read_handle);
+        let read_handle = tokio::spawn(read::<A>(
+            receiver,
+            send_join.clone(),
+            send_receiver,
+            send_close,
+            conn,
+        ));
+        let mut write_handle = tokio::spawn(write::<A>(
+            sender,
+            recv_receiver,
+            recv_close,
+            encoding,
+            heartbeat,
+        ));
+        let read_res = read_handle.await;
+        // The reader has dropped its end of the close channel, so the writer
+        // is sending whatever close frame it was given. Waiting for it drops
+        // our end of the message channel, which is how the actor tells this
+        // disconnect from a newer connection's.
+        match tokio::time::timeout(CLOSE_GRACE, &mut write_handle).await {
+            Ok(Ok(())) => (),
+            Ok(Err(err)) => error!(error = %err, "Write handler failed"),
+            Err(_) => {
+                write_handle.abort();
+                let _ = write_handle.await;
+            }
+        }
+        gauge!(WS_CONNECTIONS).decrement(1);
         let user_id = match read_res {
             Ok(Some(user_id)) => user_id,
             Ok(None) => {
@@ -50,47 +131,210 @@ pub async fn ws_handler<A: Action>(
                 return;
             }
         };
-        if let Err(err) = write_res {
-            error!(%user_id, error = %err, "Write handler failed");
-        }
         debug!(%user_id, "WebSocket closed, sending disconnect");
-        if let Err(err) = send_join.send(JoinReq::Disconnect { user_id }) {
+        if let Err(err) = send_join
+            .as_async()
+            .send(JoinReq::Disconnect { user_id })
+            .await
+        {
+            counter!(WS_SEND_FAILURES, "target" => "join").increment(1);
             error!(%user_id, error = %err, "Failed to send disconnect");
         }
     })
 }
 
+/// What the reader knows about its socket.
+struct Conn {
+    ip: IpAddr,
+    encoding: Encoding,
+    protocol: ProtocolVersion,
+    heartbeat: Heartbeat,
+    msg_bound: usize,
+    limiters: Arc<Limiters>,
+}
+
+/// The close frame the server ends a connection with.
+fn close_frame(code: u16, reason: &'static str) -> CloseFrame {
+    CloseFrame {
+        code,
+        reason: reason.into(),
+    }
+}
+
+/// Server-driven pings for one socket. Each ping carries the time it was sent,
+/// relative to when the socket opened, so its pong yields the round trip.
+#[derive(Clone, Copy)]
+struct Heartbeat {
+    interval: Duration,
+    timeout: Duration,
+    opened: Instant,
+}
+
+impl Heartbeat {
+    fn new(interval: Duration, timeout: Duration) -> Self {
+        Self {
+            interval,
+            timeout,
+            opened: Instant::now(),
+        }
+    }
+    fn ping(&self) -> Message {
+        let sent = self.opened.elapsed().as_micros() as u64;
+        Message::Ping(sent.to_be_bytes().to_vec().into())
+    }
+    fn rtt(&self, pong: &[u8]) -> Option<Duration> {
+        let sent = Duration::from_micros(u64::from_be_bytes(pong.try_into().ok()?));
+        self.opened.elapsed().checked_sub(sent)
+    }
+    /// The next frame from the client, or `None` once the socket closes or
+    /// stays silent past the timeout.
+    async fn next(&self, recv: &mut SplitStream<WebSocket>) -> Option<Message> {
+        match timeout(self.timeout, recv.next()).await {
+            Ok(Some(Ok(msg))) => Some(msg),
+            Ok(_) => None,
+            Err(_) => {
+                counter!(WS_TIMEOUTS).increment(1);
+                info!("No heartbeat from client, closing WebSocket");
+                None
+            }
+        }
+    }
+}
+
 async fn read<A: Action>(
     mut recv: SplitStream<WebSocket>,
     send_join: Sender<JoinReq<A>>,
-    send_receiver: oneshot::Sender<AsyncReceiver<A::Msg>>,
+    send_receiver: oneshot::Sender<(Inbox<A::Msg>, u16)>,
+    send_close: oneshot::Sender<CloseFrame>,
+    conn: Conn,
 ) -> Option<UserId> {
-    match wait_join(&mut recv, send_join).await {
-        Ok((user_id, send_action, recv_server_msg)) => {
-            if let Err(_err) = send_receiver.send(recv_server_msg) {
+    let Conn {
+        ip,
+        encoding,
+        protocol,
+        heartbeat,
+        ref limiters,
+        ..
+    } = conn;
+    match wait_join(&mut recv, &send_join, &conn).await {
+        Ok((
+            Seat {
+                user_id,
+                send_action,
+                recv: recv_server_msg,
+                active,
+            },
+            peer,
+        )) => {
+            if let Err(_err) = send_receiver.send((recv_server_msg, peer.version)) {
                 error!(%user_id, "Failed to send receiver");
                 return Some(user_id);
             }
-            while let Some(Ok(msg)) = recv.next().await {
-                let bytes = msg.into_data();
-                match postcard::from_bytes(&bytes) {
+            while let Some(msg) = heartbeat.next(&mut recv).await {
+                let msg = match msg {
+                    Message::Pong(pong) => {
+                        if let Some(rtt) = heartbeat.rtt(&pong) {
+                            histogram!(WS_RTT_SECONDS).record(rtt);
+                            if let Err(err) = send_join
+                                .as_async()
+                                .send(JoinReq::Latency { user_id, rtt })
+                                .await
+                            {
+                                counter!(WS_SEND_FAILURES, "target" => "join").increment(1);
+                                error!(%user_id, error = %err, "Failed to send latency");
+                            }
+                        }
+                        continue;
+                    }
+                    // Answered by axum itself.
+                    Message::Ping(_) => continue,
+                    Message::Close(_) => break,
+                    msg => msg,
+                };
+                if !limiters.action(user_id) {
+                    counter!(WS_REJECTED, "reason" => "rate").increment(1);
+                    warn!(%user_id, "Too many actions, closing WebSocket");
+                    let _ = send_close.send(close_frame(close_code::POLICY, "Too many actions"));
+                    break;
+                }
+                match decode(encoding, msg, limiters.max_frame_bytes) {
+                    Ok(ClientMsg::Action::<A> { .. }) if !active.load(Ordering::Relaxed) => {
+                        counter!(WS_REJECTED, "reason" => "inactive").increment(1);
+                        debug!(%user_id, "Dropped action from a session that may not act");
+                    }
                     Ok(ClientMsg::Action::<A> { action }) => {
                         debug!(%user_id, "Received action from client");
-                        if let Err(err) = send_action.send((action, user_id)) {
+                        if let Err(err) = send_action.as_async().send((action, user_id)).await {
+                            counter!(WS_SEND_FAILURES, "target" => "actor").increment(1);
                             error!(%user_id, error = %err, "Failed to send action to actor");
                         }
                     }
+                    Ok(ClientMsg::RequestHint) => {
+                        debug!(%user_id, "Received hint request from client");
+                        if let Err(err) = send_join.as_async().send(JoinReq::Hint { user_id }).await
+                        {
+                            counter!(WS_SEND_FAILURES, "target" => "join").increment(1);
+                            error!(%user_id, error = %err, "Failed to send hint request");
+                        }
+                    }
                     Ok(_other) => {
+                        counter!(WS_REJECTED, "reason" => "unexpected").increment(1);
                         warn!(%user_id, "Received unexpected message");
                     }
+                    Err(WireError::TooLarge { len, max }) => {
+                        counter!(WS_REJECTED, "reason" => "too_large").increment(1);
+                        warn!(%user_id, len, max, "Message too large, closing WebSocket");
+                        let _ = send_close.send(close_frame(close_code::SIZE, "Message too large"));
+                        break;
+                    }
                     Err(err) => {
+                        counter!(WS_REJECTED, "reason" => "malformed").increment(1);
                         error!(error = %err, "Failed to deserialize action");
                     }
                 };
             }
             Some(user_id)
         }
+        Err(Error::IncompatibleVersion {
+            version,
+            min_version,
+        }) => {
+            counter!(WS_REJECTED, "reason" => "version").increment(1);
+            info!(version, min_version, "Rejected incompatible client");
+            if version == ProtocolVersion::LEGACY.version {
+                // Clients from before the handshake cannot decode the reply,
+                // so they only get the reason in the close frame.
+                let _ = send_close.send(close_frame(
+                    close_code::POLICY,
+                    "Incompatible protocol version, please reload",
+                ));
+                return None;
+            }
+            // The writer sends this one message and closes the socket once
+            // the outbox is dropped.
+            let (send_msg, recv_msg) = mailbox(1);
+            send_msg.push(A::incompatible_msg(protocol.version, protocol.min_version));
+            // Every revision since the handshake knows this message as it is.
+            let _ = send_receiver.send((recv_msg, protocol.version));
+            None
+        }
+        Err(Error::RateLimited) => {
+            counter!(WS_REJECTED, "reason" => "rate").increment(1);
+            warn!(%ip, "Too many connections or joins, closing WebSocket");
+            let _ = send_close.send(close_frame(
+                close_code::POLICY,
+                "Too many connections or joins",
+            ));
+            None
+        }
+        Err(Error::TooLarge) => {
+            counter!(WS_REJECTED, "reason" => "too_large").increment(1);
+            warn!(ip = %conn.ip, "Message too large, closing WebSocket");
+            let _ = send_close.send(close_frame(close_code::SIZE, "Message too large"));
+            None
+        }
         Err(err) => {
+            counter!(WS_REJECTED, "reason" => "join").increment(1);
             error!(error = %err, "Failed to join");
             None
         }
@@ -99,71 +343,170 @@ async fn read<A: Action>(
 
 async fn write<A: Action>(
     mut send: SplitSink<WebSocket, Message>,
-    recv_receiver: oneshot::Receiver<AsyncReceiver<A::Msg>>,
+    recv_receiver: oneshot::Receiver<(Inbox<A::Msg>, u16)>,
+    mut recv_close: oneshot::Receiver<CloseFrame>,
+    encoding: Encoding,
+    heartbeat: Heartbeat,
 ) {
-    match recv_receiver.await {
-        Ok(recv) => {
-            while let Ok(msg) = recv.recv().await {
-                match postcard::to_stdvec(&msg) {
-                    Ok(bytes) => {
-                        if let Err(err) = send.send(Message::Binary(bytes.into())).await {
-                            error!(error = %err, "Failed to send message to client");
-                        }
+    // Pings start with the socket, so a client that never joins is timed
+    // out like one that stops answering at the table.
+    let mut ping = interval(heartbeat.interval);
+    ping.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut recv_receiver = Some(recv_receiver);
+    let mut inbox: Option<Inbox<A::Msg>> = None;
+    // The revision the client announced, which messages are shaped for.
+    let mut peer = 0;
+    let reason = loop {
+        let frame = tokio::select! {
+            recv = async { recv_receiver.as_mut().unwrap().await }, if recv_receiver.is_some() => {
+                recv_receiver = None;
+                match recv {
+                    Ok((recv, version)) => {
+                        inbox = Some(recv);
+                        peer = version;
+                        continue;
                     }
+                    // The reader gave up before joining, perhaps saying why.
+                    Err(_) => break (&mut recv_close).await.ok(),
+                }
+            }
+            msg = async { inbox.as_ref().unwrap().recv().await }, if inbox.is_some() => {
+                let Some(msg) = msg else {
+                    debug!("Server message channel closed, closing WebSocket");
+                    break None;
+                };
+                match encode(encoding, &A::downgrade(msg, peer)) {
+                    Ok(frame) => frame,
                     Err(err) => {
                         error!(error = %err, "Failed to serialize server message");
+                        continue;
                     }
                 }
             }
+            // Resolves once the reader is done, with a reason if it is the
+            // one ending the connection.
+            reason = &mut recv_close => break reason.ok(),
+            _ = ping.tick() => heartbeat.ping(),
+        };
+        if let Err(err) = send.send(frame).await {
+            // The reader sees the socket close and disconnects the user.
+            counter!(WS_SEND_FAILURES, "target" => "client").increment(1);
+            debug!(error = %err, "Failed to send message to client, stopping writer");
+            return;
         }
-        Err(err) => {
-            error!(error = %err, "Failed to receive ServerMsg receiver");
+    };
+    // Flush what was already queued, such as a version rejection.
+    let queued = recv_receiver.and_then(|mut r| r.try_recv().ok());
+    if let Some((recv, peer)) = queued.or(inbox.map(|recv| (recv, peer))) {
+        while let Some(msg) = recv.try_recv() {
+            let Ok(frame) = encode(encoding, &A::downgrade(msg, peer)) else {
+                continue;
+            };
+            if send.send(frame).await.is_err() {
+                return;
+            }
         }
     }
+    if let Err(err) = send.send(Message::Close(reason)).await {
+        debug!(error = %err, "Failed to close WebSocket");
+    }
 }
 
 async fn wait_join<A: Action>(
     recv: &mut SplitStream<WebSocket>,
-    send_join: Sender<JoinReq<A>>,
-) -> Result<(UserId, Sender<(A, UserId)>, AsyncReceiver<A::Msg>), Error> {
-    while let Some(Ok(msg)) = recv.next().await {
-        let bytes = msg.into_data();
-        match postcard::from_bytes(&bytes) {
-            Ok(ClientMsg::Join::<A> {
-                client_token,
-                lobby,
+    send_join: &Sender<JoinReq<A>>,
+    conn: &Conn,
+) -> Result<(Seat<A>, ProtocolVersion), Error> {
+    let protocol = conn.protocol;
+    let mut peer = ProtocolVersion::LEGACY;
+    // The token is charged for the connection on its first request for a
+    // seat; retries on the same socket only count as joins.
+    let mut charged = false;
+    while let Some(msg) = conn.heartbeat.next(recv).await {
+        let msg = match msg {
+            // Pongs only need to arrive; there is no table to report them to yet.
+            Message::Pong(_) | Message::Ping(_) => continue,
+            Message::Close(_) => break,
+            msg => msg,
+        };
+        match decode(conn.encoding, msg, conn.limiters.max_frame_bytes) {
+            Ok(ClientMsg::Hello::<A> {
+                version,
+                min_version,
             }) => {
-                let user_id = client_token;
-                info!(%user_id, lobby = ?lobby, "Client join request");
-                let (send_server_msg, recv_server_msg) = kanal::bounded(MSG_BOUND);
-                let (send_sender, recv_sender) = kanal::bounded(MSG_BOUND);
-                let join_req = JoinReq::<A>::Connect {
-                    user_id,
-                    actor_id: lobby,
-                    send_server_msg,
-                    send_sender,
+                debug!(version, min_version, "Client hello");
+                peer = ProtocolVersion {
+                    version,
+                    min_version,
                 };
-                match send_join.send(join_req) {
-                    Ok(()) => match recv_sender.to_async().recv().await {
-                        Ok(send_action) => {
-                            return Ok((user_id, send_action, recv_server_msg.to_async()));
-                        }
-                        Err(err) => {
-                            error!(%user_id, error = %err, "Failed to receive action sender");
-                        }
-                    },
-                    Err(err) => {
-                        error!(%user_id, error = %err, "Failed to send join request");
-                    }
+                if !protocol.compatible(peer) {
+                    return Err(Error::IncompatibleVersion {
+                        version,
+                        min_version,
+                    });
                 }
             }
-            Ok(other) => {
-                warn!("Received unexpected message: {other:?}");
+            Ok(msg) => {
+                let (client_token, destination) = match Destination::requested(msg) {
+                    Ok(request) => request,
+                    Err(other) => {
+                        counter!(WS_REJECTED, "reason" => "unexpected").increment(1);
+                        warn!("Received unexpected message: {other:?}");
+                        continue;
+                    }
+                };
+                // Clients from before the handshake join without a hello.
+                if !protocol.compatible(peer) {
+                    return Err(Error::IncompatibleVersion {
+                        version: peer.version,
+                        min_version: peer.min_version,
+                    });
+                }
+                if !std::mem::replace(&mut charged, true) && !conn.limiters.connection(client_token)
+                {
+                    return Err(Error::RateLimited);
+                }
+                if !conn.limiters.join(conn.ip, client_token) {
+                    return Err(Error::RateLimited);
+                }
+                // A failed join is logged; the client may try again.
+                if let Ok(seat) =
+                    connect(send_join, client_token, destination, conn.msg_bound).await
+                {
+                    return Ok((seat, peer));
+                }
             }
+            Err(WireError::TooLarge { .. }) => return Err(Error::TooLarge),
             Err(err) => {
+                counter!(WS_REJECTED, "reason" => "malformed").increment(1);
                 error!(error = %err, "Failed to deserialize action");
             }
         };
     }
     Err(Error::JoinError)
 }
+
+fn encode<T: Serialize>(encoding: Encoding, msg: &T) -> Result<Message, WireError> {
+    Ok(match encoding {
+        Encoding::Postcard => Message::Binary(postcard::to_stdvec(msg)?.into()),
+        Encoding::Json => Message::Text(serde_json::to_string(msg)?.into()),
+    })
+}
+
+fn decode<T: DeserializeOwned>(
+    encoding: Encoding,
+    msg: Message,
+    max: usize,
+) -> Result<T, WireError> {
+    let bytes = msg.into_data();
+    if bytes.len() > max {
+        return Err(WireError::TooLarge {
+            len: bytes.len(),
+            max,
+        });
+    }
+    Ok(match encoding {
+        Encoding::Postcard => postcard::from_bytes(&bytes)?,
+        Encoding::Json => serde_json::from_slice(&bytes)?,
+    })
+}
@@ -0,0 +1,298 @@
+use actor::{ActorId, UserId};
+use arrayvec::ArrayVec;
+use crossterm::event::{KeyCode, KeyEvent};
+use game::{Difficulty, Hint, JsCard, Outcome, PROTOCOL_VERSION, Phase, RegicideAction, ServerMsg};
+use std::collections::BTreeSet;
+
+/// What the main loop should do after a key press.
+pub enum Command {
+    Refresh,
+    Join(Option<ActorId>),
+    Send(RegicideAction),
+    Hint,
+    Leave,
+    Quit,
+}
+
+/// The last game state received from the server.
+pub struct Table {
+    pub phase: Phase,
+    pub players: Vec<(UserId, u8)>,
+    pub latency: Vec<(UserId, u32)>,
+    pub library_size: u8,
+    pub discard_size: u8,
+    pub damage: u8,
+    pub enemy: JsCard,
+    pub hand: Vec<JsCard>,
+    pub resolving: Vec<Vec<JsCard>>,
+}
+
+pub enum Screen {
+    Lobbies {
+        lobbies: Vec<(ActorId, u8)>,
+        selected: usize,
+    },
+    Game {
+        lobby: Option<ActorId>,
+        table: Option<Table>,
+        /// Whether the party won, and how the game went if the server says.
+        result: Option<(bool, Option<Box<Outcome>>)>,
+    },
+}
+
+pub struct App {
+    pub token: UserId,
+    pub screen: Screen,
+    pub status: String,
+    pub selected: BTreeSet<u8>,
+    pub hints: Vec<Hint>,
+    /// Hand covered until the seat at the keyboard confirms it is them.
+    pub hidden: bool,
+}
+
+impl App {
+    pub fn new(token: UserId) -> Self {
+        Self {
+            token,
+            screen: Screen::Lobbies {
+                lobbies: Vec::new(),
+                selected: 0,
+            },
+            status: String::new(),
+            selected: BTreeSet::new(),
+            hints: Vec::new(),
+            hidden: false,
+        }
+    }
+    pub fn lobbies(&mut self, list: Vec<(ActorId, u8)>) {
+        self.status = format!("{} open lobbies", list.len());
+        self.screen = Screen::Lobbies {
+            lobbies: list,
+            selected: 0,
+        };
+    }
+    pub fn joining(&mut self, lobby: Option<ActorId>) {
+        self.selected.clear();
+        self.hints.clear();
+        self.screen = Screen::Game {
+            lobby,
+            table: None,
+            result: None,
+        };
+    }
+    /// Hands the keyboard to `seat` in a local game, covering the table until
+    /// they press enter.
+    pub fn pass(&mut self, seat: UserId) {
+        self.token = seat;
+        self.hidden = true;
+        self.selected.clear();
+        self.hints.clear();
+        self.status = format!("Pass to {seat}");
+    }
+    /// Whose turn it is, if anyone's.
+    pub fn acting(&self) -> Option<UserId> {
+        match &self.screen {
+            Screen::Game {
+                table: Some(table), ..
+            } => match table.phase {
+                Phase::Play(id) | Phase::Jester(id) | Phase::Defend(id) => Some(id),
+                Phase::Victory | Phase::Defeat => None,
+            },
+            _ => None,
+        }
+    }
+    pub fn server(&mut self, msg: ServerMsg) {
+        let Screen::Game {
+            lobby,
+            table,
+            result,
+        } = &mut self.screen
+        else {
+            return;
+        };
+        match msg {
+            ServerMsg::Join { joined } => {
+                *lobby = Some(joined);
+                self.status = format!("Joined lobby {joined}");
+            }
+            ServerMsg::Game {
+                phase,
+                players,
+                latency,
+                library_size,
+                discard_size,
+                damage,
+                enemy,
+                hand,
+                resolving,
+            } => {
+                if table.as_ref().is_some_and(|t| t.phase != phase) {
+                    self.hints.clear();
+                }
+                // A rematch was dealt.
+                *result = None;
+                self.selected.retain(|i| (*i as usize) < hand.len());
+                *table = Some(Table {
+                    phase,
+                    players,
+                    latency,
+                    library_size,
+                    discard_size,
+                    damage,
+                    enemy,
+                    hand,
+                    resolving,
+                });
+            }
+            ServerMsg::Finished {
+                outcome,
+                rematch,
+                seats,
+            } => {
+                *result = Some((outcome.victory, Some(Box::new(outcome))));
+                self.status = rematch_status(&rematch, seats);
+            }
+            // From servers older than `Finished`.
+            ServerMsg::Victory { rematch, seats } => {
+                *result = Some((true, None));
+                self.status = rematch_status(&rematch, seats);
+            }
+            ServerMsg::Defeat { rematch, seats } => {
+                *result = Some((false, None));
+                self.status = rematch_status(&rematch, seats);
+            }
+            ServerMsg::ServerShutdown { reconnect_after } => {
+                self.status = format!("Server restarting, rejoin in {reconnect_after}s");
+            }
+            ServerMsg::Hint { hints } => {
+                if hints.is_empty() {
+                    self.status = "Nothing to suggest right now".to_string();
+                }
+                self.hints = hints;
+            }
+            ServerMsg::IncompatibleVersion {
+                version,
+                min_version,
+            } => {
+                self.status = format!(
+                    "Server speaks protocol {version} and needs {min_version}+, \
+                     this client is {PROTOCOL_VERSION}; please upgrade"
+                );
+            }
+            ServerMsg::Queued {
+                waiting, players, ..
+            } => {
+                self.status = format!("Waiting for a quick match, {waiting} of {players} here");
+            }
+        }
+    }
+    pub fn key(&mut self, key: KeyEvent) -> Option<Command> {
+        if key.code == KeyCode::Char('q') {
+            return Some(Command::Quit);
+        }
+        if self.hidden {
+            if key.code == KeyCode::Enter {
+                self.hidden = false;
+                self.status.clear();
+            }
+            return None;
+        }
+        match &mut self.screen {
+            Screen::Lobbies { lobbies, selected } => match key.code {
+                KeyCode::Up | KeyCode::Char('k') => {
+                    *selected = selected.saturating_sub(1);
+                    None
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    *selected = (*selected + 1).min(lobbies.len().saturating_sub(1));
+                    None
+                }
+                KeyCode::Enter => lobbies
+                    .get(*selected)
+                    .map(|(id, _)| Command::Join(Some(*id))),
+                KeyCode::Char('n') => Some(Command::Join(None)),
+                KeyCode::Char('r') => Some(Command::Refresh),
+                _ => None,
+            },
+            Screen::Game { .. } => self.game_key(key),
+        }
+    }
+    fn game_key(&mut self, key: KeyEvent) -> Option<Command> {
+        let action = |action| Some(Command::Send(action));
+        let jester = matches!(self.acting_phase(), Some(Phase::Jester(_)));
+        match key.code {
+            KeyCode::Esc if !self.selected.is_empty() || !self.hints.is_empty() => {
+                self.selected.clear();
+                self.hints.clear();
+                None
+            }
+            KeyCode::Esc => Some(Command::Leave),
+            KeyCode::Char(c @ '1'..='8') if jester => {
+                let Screen::Game {
+                    table: Some(table), ..
+                } = &self.screen
+                else {
+                    return None;
+                };
+                let index = c as usize - '1' as usize;
+                let (player, _) = table.players.get(index)?;
+                action(RegicideAction::Jester { player: *player })
+            }
+            KeyCode::Char(c @ '1'..='8') => {
+                let index = c as u8 - b'1';
+                if !self.selected.remove(&index) {
+                    self.selected.insert(index);
+                }
+                None
+            }
+            KeyCode::Char('p') => {
+                let cards: ArrayVec<u8, 4> = self.selected.iter().copied().take(4).collect();
+                self.selected.clear();
+                action(RegicideAction::Play { cards })
+            }
+            KeyCode::Char('y') => action(RegicideAction::Play {
+                cards: ArrayVec::new(),
+            }),
+            KeyCode::Char('d') => {
+                let cards: ArrayVec<u8, 8> = self.selected.iter().copied().collect();
+                self.selected.clear();
+                action(RegicideAction::Discard { cards })
+            }
+            KeyCode::Char('s') => action(RegicideAction::Init),
+            KeyCode::Char('b') => action(RegicideAction::AddBot {
+                difficulty: Difficulty::Normal,
+            }),
+            KeyCode::Char('h') => Some(Command::Hint),
+            KeyCode::Char(c @ ('r' | 'R')) if self.ended() => {
+                action(RegicideAction::Rematch { rotate: c == 'R' })
+            }
+            _ => None,
+        }
+    }
+    fn ended(&self) -> bool {
+        matches!(
+            self.screen,
+            Screen::Game {
+                result: Some(_),
+                ..
+            }
+        )
+    }
+    /// The phase, but only when it is our move.
+    fn acting_phase(&self) -> Option<Phase> {
+        match &self.screen {
+            Screen::Game {
+                table: Some(table), ..
+            } if self.acting() == Some(self.token) => Some(table.phase),
+            _ => None,
+        }
+    }
+}
+
+/// How the vote for another game at this table stands.
+fn rematch_status(rematch: &[UserId], seats: u8) -> String {
+    match rematch.len() {
+        0 => String::new(),
+        votes => format!("{votes} of {seats} want a rematch"),
+    }
+}
@@ -0,0 +1,62 @@
+use actor::{Action, UserId};
+use game::{
+    GameState, Phase, RegicideAction, Ruleset, ServerMsg, SharedState, UserState, View, hints,
+};
+use std::collections::HashMap;
+
+/// A whole game in this process, driven straight through
+/// [`RegicideAction::update`]. Seats share one keyboard.
+pub struct Local {
+    shared: SharedState,
+    user: HashMap<UserId, UserState>,
+}
+
+impl Local {
+    /// Seats `players` and deals under `rules`.
+    pub fn new(mut rules: Ruleset, players: u8) -> Self {
+        rules.min_players = players;
+        rules.max_players = players;
+        let mut shared = RegicideAction::shared(&rules);
+        let mut user: HashMap<UserId, UserState> = (1..=players)
+            .map(|seat| (seat_id(seat), UserState::default()))
+            .collect();
+        RegicideAction::Init.update(&mut shared, &mut user, seat_id(1));
+        Self { shared, user }
+    }
+    /// The seat that has to act next, or `None` once the game is over.
+    pub fn acting(&self) -> Option<UserId> {
+        match &self.shared.game {
+            GameState::Init {
+                phase: Phase::Play(id) | Phase::Jester(id) | Phase::Defend(id),
+                ..
+            } => Some(*id),
+            _ => None,
+        }
+    }
+    /// What the server would send `seat` right now.
+    pub fn msg(&self, seat: UserId) -> Option<ServerMsg> {
+        RegicideAction::msg(&self.shared, &self.user)
+            .into_iter()
+            .find_map(|(id, msg)| (id == seat).then_some(msg))
+    }
+    pub fn act(&mut self, seat: UserId, action: RegicideAction) {
+        action.update(&mut self.shared, &mut self.user, seat);
+    }
+    /// Casts the same vote for every seat.
+    pub fn rematch(&mut self, vote: RegicideAction) {
+        let seats: Vec<_> = self.user.keys().copied().collect();
+        seats
+            .into_iter()
+            .for_each(|seat| self.act(seat, vote.clone()));
+    }
+    pub fn hint(&self, seat: UserId) -> ServerMsg {
+        let hints = View::new(&self.shared, &self.user, seat)
+            .map(|view| hints(&view))
+            .unwrap_or_default();
+        ServerMsg::Hint { hints }
+    }
+}
+
+pub fn seat_id(seat: u8) -> UserId {
+    UserId::from(&format!("player-{seat}")).expect("seat id fits")
+}
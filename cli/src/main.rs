@@ -0,0 +1,185 @@
+mod app;
+mod error;
+mod local;
+mod ui;
+
+pub use app::*;
+pub use error::*;
+pub use local::*;
+
+use actor::UserId;
+use clap::Parser;
+use client::RegicideClient;
+use crossterm::event::{Event, EventStream, KeyEventKind};
+use futures_util::StreamExt;
+use game::{MAX_PLAYERS, RegicideAction, Ruleset};
+use std::time::Duration;
+
+/// Redraw interval, so connection changes show without a key press.
+const TICK: Duration = Duration::from_millis(500);
+
+/// Plays Regicide from the terminal, against a server or hot-seat.
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    /// Base URL of the server.
+    #[arg(
+        short,
+        long,
+        env = "REGICIDE_SERVER",
+        default_value = "http://127.0.0.1:3000"
+    )]
+    server: String,
+    /// Client token to rejoin with; a fresh one is made when omitted.
+    #[arg(short, long, env = "REGICIDE_CLIENT_TOKEN")]
+    token: Option<UserId>,
+    /// Play a hot-seat game with this many seats instead of connecting.
+    #[arg(long, value_parser = clap::value_parser!(u8).range(1..=MAX_PLAYERS as i64))]
+    local: Option<u8>,
+    /// Seed the local deal so it can be replayed.
+    #[arg(long, requires = "local")]
+    seed: Option<u64>,
+    /// Override the number of jesters in a local game.
+    #[arg(long, requires = "local")]
+    jesters: Option<u8>,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    let cli = Cli::parse();
+    let token = cli.token.unwrap_or_else(|| {
+        UserId::from(&uuid::Uuid::now_v7().simple().to_string()).expect("uuid fits")
+    });
+    let mut terminal = ratatui::init();
+    let result = match cli.local {
+        Some(players) => {
+            let rules = Ruleset {
+                jesters: cli.jesters,
+                seed: cli.seed,
+                ..Ruleset::default()
+            };
+            run_local(&mut terminal, rules, players).await
+        }
+        None => run(&mut terminal, &cli.server, token).await,
+    };
+    ratatui::restore();
+    result
+}
+
+async fn run(
+    terminal: &mut ratatui::DefaultTerminal,
+    server: &str,
+    token: UserId,
+) -> Result<(), Error> {
+    let mut app = App::new(token);
+    let mut events = EventStream::new();
+    let mut client = RegicideClient::new(server)?;
+    let mut tick = tokio::time::interval(TICK);
+    let mut online = true;
+    refresh(&mut app, &client).await;
+    loop {
+        if client.joined() && client.connected() != online {
+            online = client.connected();
+            app.status = if online {
+                "Reconnected".to_string()
+            } else {
+                "Connection lost, reconnecting...".to_string()
+            };
+        }
+        terminal.draw(|frame| ui::draw(frame, &app))?;
+        let command = tokio::select! {
+            event = events.next() => match event {
+                Some(Ok(Event::Key(key))) if key.kind == KeyEventKind::Press => app.key(key),
+                Some(Ok(_)) => None,
+                Some(Err(e)) => return Err(e.into()),
+                None => Some(Command::Quit),
+            },
+            Some(msg) = client.next(), if client.joined() => {
+                app.server(msg);
+                None
+            }
+            _ = tick.tick() => None,
+        };
+        match command {
+            Some(Command::Refresh) => refresh(&mut app, &client).await,
+            Some(Command::Join(lobby)) => match client.join(lobby, token).await {
+                Ok(()) => {
+                    online = true;
+                    app.status = "Joining...".to_string();
+                    app.joining(lobby);
+                }
+                Err(e) => app.status = e.to_string(),
+            },
+            Some(Command::Send(action)) => {
+                if let Err(e) = client.send(action) {
+                    app.status = e.to_string();
+                }
+            }
+            Some(Command::Hint) => {
+                if let Err(e) = client.request_hint() {
+                    app.status = e.to_string();
+                }
+            }
+            Some(Command::Leave) => {
+                client.leave();
+                refresh(&mut app, &client).await;
+            }
+            Some(Command::Quit) => return Ok(()),
+            None => {}
+        }
+    }
+}
+
+async fn refresh(app: &mut App, client: &RegicideClient) {
+    match client.lobbies().await {
+        Ok(list) => app.lobbies(list),
+        Err(e) => app.status = e.to_string(),
+    }
+}
+
+async fn run_local(
+    terminal: &mut ratatui::DefaultTerminal,
+    rules: Ruleset,
+    players: u8,
+) -> Result<(), Error> {
+    let mut game = Local::new(rules, players);
+    let mut app = App::new(seat_id(1));
+    app.joining(None);
+    let mut events = EventStream::new();
+    sync(&game, &mut app, players > 1);
+    loop {
+        terminal.draw(|frame| ui::draw(frame, &app))?;
+        let command = match events.next().await {
+            Some(Ok(Event::Key(key))) if key.kind == KeyEventKind::Press => app.key(key),
+            Some(Ok(_)) => None,
+            Some(Err(e)) => return Err(e.into()),
+            None => Some(Command::Quit),
+        };
+        match command {
+            Some(Command::Send(action @ RegicideAction::Rematch { .. })) => {
+                // Everyone shares the keyboard, so one vote speaks for all.
+                game.rematch(action);
+                sync(&game, &mut app, players > 1);
+            }
+            Some(Command::Send(action)) => {
+                game.act(app.token, action);
+                sync(&game, &mut app, false);
+            }
+            Some(Command::Hint) => app.server(game.hint(app.token)),
+            Some(Command::Leave | Command::Quit) => return Ok(()),
+            Some(Command::Refresh | Command::Join(_)) | None => {}
+        }
+    }
+}
+
+/// Shows the table to whoever acts next, hiding it first if the seat changed.
+fn sync(game: &Local, app: &mut App, hide: bool) {
+    if let Some(seat) = game.acting()
+        && (hide || seat != app.token)
+    {
+        app.pass(seat);
+    }
+    if let Some(msg) = game.msg(app.token) {
+        app.server(msg);
+    }
+}
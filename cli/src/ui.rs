@@ -0,0 +1,269 @@
+use crate::{App, Screen, Table};
+use actor::UserId;
+use game::{JsCard, Outcome, Phase, Suit};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, List, ListItem, ListState, Paragraph, Wrap},
+};
+
+pub fn card(card: &JsCard) -> Span<'static> {
+    let rank = match card.rank.as_str() {
+        "Joker" => return Span::styled("Jester", Style::new().fg(Color::Magenta)),
+        rank if rank.ends_with("Ace") => "A".to_string(),
+        rank if rank.ends_with("Jack") => "J".to_string(),
+        rank if rank.ends_with("Queen") => "Q".to_string(),
+        rank if rank.ends_with("King") => "K".to_string(),
+        _ => card.value.to_string(),
+    };
+    let (suit, color) = match card.suit {
+        Some(Suit::Heart) => ("♥", Color::Red),
+        Some(Suit::Diamond) => ("♦", Color::Red),
+        Some(Suit::Spade) => ("♠", Color::White),
+        Some(Suit::Club) => ("♣", Color::White),
+        None => ("", Color::Magenta),
+    };
+    Span::styled(format!("{rank}{suit}"), Style::new().fg(color).bold())
+}
+
+fn who(id: UserId, me: UserId) -> String {
+    if id == me {
+        "you".to_string()
+    } else {
+        id.to_string()
+    }
+}
+
+pub fn phase(phase: Phase, me: UserId) -> String {
+    match phase {
+        Phase::Play(id) => format!("{} to play", who(id, me)),
+        Phase::Jester(id) => format!("{} picks who plays next", who(id, me)),
+        Phase::Defend(id) => format!("{} must discard to survive", who(id, me)),
+        Phase::Victory => "Victory".to_string(),
+        Phase::Defeat => "Defeat".to_string(),
+    }
+}
+
+pub fn draw(frame: &mut Frame, app: &App) {
+    let [header, body, footer] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Min(0),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    frame.render_widget(
+        Line::from(vec![
+            "Regicide ".bold(),
+            Span::raw(format!("as {} ", app.token)).dim(),
+            Span::raw(app.status.as_str()),
+        ]),
+        header,
+    );
+    let help = match &app.screen {
+        _ if app.hidden => "enter reveal  q quit",
+        Screen::Lobbies { .. } => "↑/↓ select  enter join  n new lobby  r refresh  q quit",
+        Screen::Game { table: None, .. } => "s start  b add bot  esc leave  q quit",
+        Screen::Game { .. } => {
+            "1-8 select  p play  y yield  d discard  h hint  esc clear/leave  q quit"
+        }
+    };
+    frame.render_widget(Line::from(help).dim(), footer);
+
+    if app.hidden {
+        frame.render_widget(
+            Paragraph::new(format!(
+                "Pass the keyboard to {}.\n\nPress enter when nobody else is looking.",
+                app.token
+            ))
+            .block(Block::bordered().title("Hand hidden")),
+            body,
+        );
+        return;
+    }
+
+    match &app.screen {
+        Screen::Lobbies { lobbies, selected } => {
+            let items: Vec<ListItem> = lobbies
+                .iter()
+                .map(|(id, players)| ListItem::new(format!("{id}  {players} seated")))
+                .collect();
+            let list = List::new(items)
+                .block(Block::bordered().title("Open lobbies"))
+                .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+            let mut state = ListState::default().with_selected(Some(*selected));
+            frame.render_stateful_widget(list, body, &mut state);
+        }
+        Screen::Game {
+            lobby,
+            table,
+            result,
+        } => {
+            if let Some((victory, outcome)) = result {
+                let mut lines = vec![Line::from(if *victory {
+                    "Victory! Every royal has fallen."
+                } else {
+                    "Defeat. The court prevails."
+                })];
+                if let Some(outcome) = outcome {
+                    if let Some(enemy) = &outcome.defeated_by {
+                        lines.push(Line::from(vec![Span::raw("Fell to "), card(enemy)]));
+                    }
+                    lines.extend(summary(outcome).into_iter().map(Line::from));
+                }
+                lines.push(Line::from(""));
+                lines.push(Line::from(
+                    "r for a rematch, R to also pass first turn along, \
+                     esc to return to the lobby list",
+                ));
+                frame.render_widget(Paragraph::new(lines).block(Block::bordered()), body);
+            } else if let Some(table) = table {
+                draw_table(frame, app, table, body);
+            } else {
+                let lobby = lobby.map(|id| id.to_string()).unwrap_or_default();
+                frame.render_widget(
+                    Paragraph::new(format!(
+                        "Waiting in lobby {lobby}\n\nPress s to start once everyone is seated, \
+                         or b to fill a seat with a bot."
+                    ))
+                    .block(Block::bordered().title("Lobby")),
+                    body,
+                );
+            }
+        }
+    }
+}
+
+fn draw_table(frame: &mut Frame, app: &App, table: &Table, area: ratatui::layout::Rect) {
+    let [top, hand_area, hints_area] = Layout::vertical([
+        Constraint::Length(9),
+        Constraint::Length(4),
+        Constraint::Min(0),
+    ])
+    .areas(area);
+    let [players_area, enemy_area, resolving_area] = Layout::horizontal([
+        Constraint::Length(28),
+        Constraint::Length(30),
+        Constraint::Min(0),
+    ])
+    .areas(top);
+
+    let acting = app.acting();
+    let players: Vec<ListItem> = table
+        .players
+        .iter()
+        .enumerate()
+        .map(|(i, (id, cards))| {
+            let mut line = Line::from(format!("{} {} ({cards})", i + 1, who(*id, app.token)));
+            if let Some((_, ms)) = table.latency.iter().find(|(player, _)| player == id) {
+                line.push_span(Span::raw(format!(" {ms}ms")).dim());
+            }
+            if Some(*id) == acting {
+                line = line.bold().yellow();
+            }
+            ListItem::new(line)
+        })
+        .collect();
+    frame.render_widget(
+        List::new(players).block(Block::bordered().title("Players")),
+        players_area,
+    );
+
+    let health = table.enemy.value as u16 * 2;
+    let enemy = vec![
+        Line::from(vec![Span::raw("Enemy  "), card(&table.enemy)]),
+        Line::from(format!(
+            "Damage {}/{health}  attack {}",
+            table.damage, table.enemy.value
+        )),
+        Line::from(format!(
+            "Library {}  discard {}",
+            table.library_size, table.discard_size
+        )),
+        Line::from(""),
+        Line::from(phase(table.phase, app.token)).bold(),
+    ];
+    frame.render_widget(
+        Paragraph::new(enemy).block(Block::bordered().title("Court")),
+        enemy_area,
+    );
+
+    let resolving: Vec<Line> = table
+        .resolving
+        .iter()
+        .map(|combo| {
+            Line::from(
+                combo
+                    .iter()
+                    .flat_map(|c| [card(c), Span::raw(" ")])
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect();
+    frame.render_widget(
+        Paragraph::new(resolving).block(Block::bordered().title("Resolving")),
+        resolving_area,
+    );
+
+    let hand: Vec<Span> = table
+        .hand
+        .iter()
+        .enumerate()
+        .flat_map(|(i, c)| {
+            let selected = app.selected.contains(&(i as u8));
+            let index = Span::raw(format!("[{}]", i + 1));
+            let index = if selected { index.reversed() } else { index };
+            [index, card(c), Span::raw("  ")]
+        })
+        .collect();
+    frame.render_widget(
+        Paragraph::new(Line::from(hand)).block(Block::bordered().title("Your hand")),
+        hand_area,
+    );
+
+    if !app.hints.is_empty() {
+        let lines: Vec<Line> = app
+            .hints
+            .iter()
+            .flat_map(|hint| {
+                let cards: Vec<Span> = hint
+                    .cards
+                    .iter()
+                    .flat_map(|c| [card(c), Span::raw(" ")])
+                    .collect();
+                std::iter::once(Line::from(cards)).chain(
+                    hint.reasons
+                        .iter()
+                        .map(|reason| Line::from(format!("  {reason}")).dim()),
+                )
+            })
+            .collect();
+        frame.render_widget(
+            Paragraph::new(lines)
+                .wrap(Wrap { trim: false })
+                .block(Block::bordered().title("Suggestions")),
+            hints_area,
+        );
+    }
+}
+
+/// The end-of-game tallies, one line each.
+fn summary(outcome: &Outcome) -> Vec<String> {
+    let stats = &outcome.stats;
+    let mut lines = vec![
+        format!("Royals beaten: {} of 12", outcome.enemies_defeated),
+        format!(
+            "{} turns, {} exact kills, {} jesters",
+            stats.turns, stats.exact_kills, stats.jesters
+        ),
+        format!("{} cards healed, {} drawn", stats.healed, stats.drawn),
+    ];
+    lines.extend(
+        stats
+            .damage
+            .iter()
+            .map(|(player, damage)| format!("{player}: {damage} damage")),
+    );
+    lines
+}
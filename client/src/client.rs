@@ -0,0 +1,271 @@
+use crate::Error;
+use actor::{ActorId, UserId};
+use futures_util::{SinkExt, Stream, StreamExt};
+use game::{
+    ACTOR_LIST_PATH, ClientMsg, ProtocolVersion, RegicideAction, ServerMsg, WS_HANDLER_PATH,
+};
+use std::{
+    pin::Pin,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::{net::TcpStream, sync::mpsc, task::JoinHandle, time::sleep};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async, tungstenite::Message};
+use tracing::{debug, warn};
+
+/// First wait before reconnecting; doubles per failed attempt.
+const MIN_BACKOFF: Duration = Duration::from_millis(250);
+const MAX_BACKOFF: Duration = Duration::from_secs(10);
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Talks to a Regicide server. After [`join`](Self::join) it yields
+/// [`ServerMsg`]s as a [`Stream`] and reconnects with the same client token
+/// whenever the socket drops, rejoining the lobby it was seated in. If the
+/// server rejects our protocol revision the stream yields
+/// [`ServerMsg::IncompatibleVersion`] and then ends.
+pub struct RegicideClient {
+    server: String,
+    ws_url: String,
+    http: reqwest::Client,
+    session: Option<Session>,
+}
+
+/// One joined game. Dropping it closes the socket and stops reconnecting.
+struct Session {
+    send: mpsc::UnboundedSender<ClientMsg<RegicideAction>>,
+    recv: mpsc::UnboundedReceiver<ServerMsg>,
+    connected: Arc<AtomicBool>,
+    task: JoinHandle<()>,
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl RegicideClient {
+    /// `server` is the base URL of the backend, e.g. `http://127.0.0.1:3000`.
+    pub fn new(server: &str) -> Result<Self, Error> {
+        let server = server.trim_end_matches('/');
+        let rest = server
+            .strip_prefix("http://")
+            .map(|rest| format!("ws://{rest}"))
+            .or_else(|| {
+                server
+                    .strip_prefix("https://")
+                    .map(|rest| format!("wss://{rest}"))
+            })
+            .ok_or_else(|| Error::Address(server.to_string()))?;
+        Ok(Self {
+            server: server.to_string(),
+            ws_url: format!("{rest}{WS_HANDLER_PATH}"),
+            http: reqwest::Client::new(),
+            session: None,
+        })
+    }
+    /// Open lobbies with the number of players seated in each.
+    pub async fn lobbies(&self) -> Result<Vec<(ActorId, u8)>, Error> {
+        let url = format!("{}{ACTOR_LIST_PATH}", self.server);
+        Ok(self
+            .http
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+    /// Joins `lobby`, or a fresh lobby when `None`, leaving any current game.
+    /// Fails if the first connection cannot be made; later drops reconnect.
+    pub async fn join(&mut self, lobby: Option<ActorId>, token: UserId) -> Result<(), Error> {
+        self.leave();
+        let socket = open(&self.ws_url, lobby, token).await?;
+        let (send, outgoing) = mpsc::unbounded_channel();
+        let (incoming, recv) = mpsc::unbounded_channel();
+        let connected = Arc::new(AtomicBool::new(true));
+        let task = tokio::spawn(run(
+            self.ws_url.clone(),
+            lobby,
+            token,
+            socket,
+            outgoing,
+            incoming,
+            connected.clone(),
+        ));
+        self.session = Some(Session {
+            send,
+            recv,
+            connected,
+            task,
+        });
+        Ok(())
+    }
+    pub fn send(&self, action: RegicideAction) -> Result<(), Error> {
+        self.send_msg(ClientMsg::Action { action })
+    }
+    pub fn request_hint(&self) -> Result<(), Error> {
+        self.send_msg(ClientMsg::RequestHint)
+    }
+    fn send_msg(&self, msg: ClientMsg<RegicideAction>) -> Result<(), Error> {
+        self.session
+            .as_ref()
+            .and_then(|session| session.send.send(msg).ok())
+            .ok_or(Error::NotJoined)
+    }
+    /// Closes the socket. The stream ends until the next join.
+    pub fn leave(&mut self) {
+        self.session = None;
+    }
+    pub fn joined(&self) -> bool {
+        self.session.is_some()
+    }
+    /// False while a dropped socket is being reopened.
+    pub fn connected(&self) -> bool {
+        self.session
+            .as_ref()
+            .is_some_and(|session| session.connected.load(Ordering::Relaxed))
+    }
+}
+
+impl Stream for RegicideClient {
+    type Item = ServerMsg;
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<ServerMsg>> {
+        match &mut self.session {
+            Some(session) => session.recv.poll_recv(cx),
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+async fn open(url: &str, lobby: Option<ActorId>, client_token: UserId) -> Result<Socket, Error> {
+    let (mut socket, _) = connect_async(url).await?;
+    let ProtocolVersion {
+        version,
+        min_version,
+    } = ProtocolVersion::CURRENT;
+    let hello = ClientMsg::<RegicideAction>::Hello {
+        version,
+        min_version,
+    };
+    socket
+        .send(Message::binary(postcard::to_stdvec(&hello)?))
+        .await?;
+    let join = ClientMsg::<RegicideAction>::Join {
+        lobby,
+        client_token,
+    };
+    socket
+        .send(Message::binary(postcard::to_stdvec(&join)?))
+        .await?;
+    Ok(socket)
+}
+
+/// Why [`pump`] returned.
+enum Closed {
+    /// The client was dropped or left.
+    Client,
+    /// The socket closed, after asking us to wait this long if it said so.
+    Socket(Option<Duration>),
+    /// The server cannot talk to this build; reconnecting will not help.
+    Incompatible,
+}
+
+async fn run(
+    url: String,
+    mut lobby: Option<ActorId>,
+    token: UserId,
+    mut socket: Socket,
+    mut outgoing: mpsc::UnboundedReceiver<ClientMsg<RegicideAction>>,
+    incoming: mpsc::UnboundedSender<ServerMsg>,
+    connected: Arc<AtomicBool>,
+) {
+    loop {
+        let wait = match pump(&mut socket, &mut outgoing, &incoming, &mut lobby).await {
+            Closed::Client => return,
+            Closed::Incompatible => {
+                warn!(%token, "Server rejected our protocol version");
+                return;
+            }
+            Closed::Socket(wait) => wait,
+        };
+        connected.store(false, Ordering::Relaxed);
+        debug!(%token, ?lobby, "Connection lost, reconnecting");
+        let mut backoff = wait.unwrap_or(MIN_BACKOFF);
+        socket = loop {
+            sleep(backoff).await;
+            // Moves queued while offline were made against stale state.
+            while outgoing.try_recv().is_ok() {}
+            match open(&url, lobby, token).await {
+                Ok(socket) => break socket,
+                Err(err) => {
+                    warn!(%token, error = %err, "Reconnect failed");
+                    backoff = (backoff * 2).clamp(MIN_BACKOFF, MAX_BACKOFF);
+                }
+            }
+        };
+        connected.store(true, Ordering::Relaxed);
+        debug!(%token, ?lobby, "Reconnected");
+    }
+}
+
+async fn pump(
+    socket: &mut Socket,
+    outgoing: &mut mpsc::UnboundedReceiver<ClientMsg<RegicideAction>>,
+    incoming: &mpsc::UnboundedSender<ServerMsg>,
+    lobby: &mut Option<ActorId>,
+) -> Closed {
+    let mut wait = None;
+    let mut incompatible = false;
+    loop {
+        tokio::select! {
+            msg = outgoing.recv() => {
+                let Some(msg) = msg else {
+                    let _ = socket.close(None).await;
+                    return Closed::Client;
+                };
+                let bytes = match postcard::to_stdvec(&msg) {
+                    Ok(bytes) => bytes,
+                    Err(err) => {
+                        warn!(error = %err, "Failed to serialize client message");
+                        continue;
+                    }
+                };
+                if socket.send(Message::binary(bytes)).await.is_err() {
+                    return Closed::Socket(wait);
+                }
+            }
+            msg = socket.next() => {
+                let bytes = match msg {
+                    Some(Ok(Message::Binary(bytes))) => bytes,
+                    Some(Ok(_)) => continue,
+                    Some(Err(_)) | None if incompatible => return Closed::Incompatible,
+                    Some(Err(_)) | None => return Closed::Socket(wait),
+                };
+                let msg = match postcard::from_bytes::<ServerMsg>(&bytes) {
+                    Ok(msg) => msg,
+                    Err(err) => {
+                        warn!(error = %err, "Failed to deserialize server message");
+                        continue;
+                    }
+                };
+                match &msg {
+                    ServerMsg::Join { joined } => *lobby = Some(*joined),
+                    ServerMsg::ServerShutdown { reconnect_after } => {
+                        wait = Some(Duration::from_secs(*reconnect_after as u64));
+                    }
+                    ServerMsg::IncompatibleVersion { .. } => incompatible = true,
+                    _ => {}
+                }
+                if incoming.send(msg).is_err() {
+                    return Closed::Client;
+                }
+            }
+        }
+    }
+}
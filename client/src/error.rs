@@ -0,0 +1,15 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Invalid server address `{0}`, expected http://host:port")]
+    Address(String),
+    #[error("Failed to list lobbies: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("WebSocket error: {0}")]
+    Ws(#[from] tokio_tungstenite::tungstenite::Error),
+    #[error("Failed to encode message: {0}")]
+    Encode(#[from] postcard::Error),
+    #[error("Not in a game")]
+    NotJoined,
+}
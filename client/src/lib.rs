@@ -0,0 +1,5 @@
+pub mod client;
+pub mod error;
+
+pub use client::*;
+pub use error::*;
@@ -40,9 +40,21 @@ export default function RootLayout({
 }
 
 function WebSocketProviderWrapper({ children }: { children: React.ReactNode }) {
-  const { setGameState } = useGame();
+  const { setGameState, setHints } = useGame();
 
   const handleMessage = (msg: ServerMsg) => {
+    if (typeof msg === 'object' && 'ServerShutdown' in msg) {
+      // The socket closes right after this; keep the board up while we reconnect.
+      return;
+    }
+    if (typeof msg === 'object' && 'IncompatibleVersion' in msg) {
+      // The connection handles this; there is no game state to show.
+      return;
+    }
+    if (typeof msg === 'object' && 'Hint' in msg) {
+      setHints(msg.Hint.hints);
+      return;
+    }
     setGameState(msg);
   };
 
@@ -1,17 +1,19 @@
 'use client';
 
-import { useState } from 'react';
+import { Fragment, useState } from 'react';
 import { useGame } from '@/contexts/GameContext';
 import { useWebSocket } from '@/contexts/WebSocketContext';
 import { useClient } from '@/contexts/ClientContext';
 import { LandingPage } from '@/components/landing/LandingPage';
 import { GameBoard } from '@/components/game/GameBoard';
 import { ReconnectingOverlay } from '@/components/shared/ReconnectingOverlay';
+import { createActionMessage } from '@/lib/websocket';
 
 export default function Home() {
   const { gameState, setGameState } = useGame();
-  const { connectionState, retryAttempt, nextRetryIn, reconnect, disconnect } = useWebSocket();
-  const { setLobbyId } = useClient();
+  const { connectionState, retryAttempt, nextRetryIn, reconnect, disconnect, sendMessage } =
+    useWebSocket();
+  const { clientToken, setLobbyId } = useClient();
   const [isJoining, setIsJoining] = useState(false);
 
   const handleJoinSuccess = () => {
@@ -28,9 +30,22 @@ export default function Home() {
   const isInGame =
     gameState !== null && typeof gameState === 'object' && 'Game' in gameState;
 
-  const isVictory = gameState === 'Victory';
-  const isDefeat = gameState === 'Defeat';
-  const isGameEnded = isVictory || isDefeat;
+  // Servers older than `Finished` end the game without an outcome.
+  const ended =
+    gameState !== null && typeof gameState === 'object'
+      ? 'Finished' in gameState
+        ? { victory: gameState.Finished.outcome.victory, ...gameState.Finished }
+        : 'Victory' in gameState
+          ? { victory: true, outcome: null, ...gameState.Victory }
+          : 'Defeat' in gameState
+            ? { victory: false, outcome: null, ...gameState.Defeat }
+            : null
+      : null;
+  const isVictory = ended?.victory === true;
+
+  const handleRematch = (rotate: boolean) => {
+    sendMessage(createActionMessage({ Rematch: { rotate } }));
+  };
 
   const showReconnecting = connectionState === 'reconnecting';
 
@@ -45,7 +60,9 @@ export default function Home() {
     );
   }
 
-  if (isGameEnded) {
+  if (ended) {
+    const { outcome, rematch, seats } = ended;
+    const voted = clientToken !== null && rematch.includes(clientToken);
     return (
       <div className="min-h-screen flex items-center justify-center">
         <div className="text-center">
@@ -57,6 +74,69 @@ export default function Home() {
               ? 'Congratulations! You defeated all the enemies!'
               : 'Better luck next time!'}
           </p>
+          {outcome && (
+            <dl className="grid grid-cols-2 gap-x-6 gap-y-1 text-left text-sm text-gray-700 dark:text-gray-300 mb-8 mx-auto w-fit">
+              <dt>Royals beaten</dt>
+              <dd>{outcome.enemies_defeated} of 12</dd>
+              {outcome.defeated_by && (
+                <>
+                  <dt>Fell to</dt>
+                  <dd>{outcome.defeated_by.rank}</dd>
+                </>
+              )}
+              <dt>Turns</dt>
+              <dd>{outcome.stats.turns}</dd>
+              <dt>Exact kills</dt>
+              <dd>{outcome.stats.exact_kills}</dd>
+              <dt>Jesters played</dt>
+              <dd>{outcome.stats.jesters}</dd>
+              <dt>Cards healed</dt>
+              <dd>{outcome.stats.healed}</dd>
+              <dt>Cards drawn</dt>
+              <dd>{outcome.stats.drawn}</dd>
+              {outcome.stats.damage.map(([player, damage]) => (
+                <Fragment key={player}>
+                  <dt>{player === clientToken ? 'Your damage' : `${player}'s damage`}</dt>
+                  <dd>{damage}</dd>
+                </Fragment>
+              ))}
+            </dl>
+          )}
+          <div className="flex justify-center gap-3 mb-4">
+            <button
+              onClick={() => handleRematch(false)}
+              disabled={voted}
+              className="
+                px-6 py-3 rounded-lg
+                bg-emerald-400 hover:bg-emerald-500 dark:bg-emerald-300 dark:hover:bg-emerald-400
+                text-gray-800 dark:text-gray-900
+                font-semibold shadow-sm hover:shadow-md
+                disabled:opacity-50 disabled:cursor-not-allowed
+                transition-all
+              "
+            >
+              Play Again
+            </button>
+            <button
+              onClick={() => handleRematch(true)}
+              disabled={voted}
+              className="
+                px-6 py-3 rounded-lg
+                bg-gray-200 hover:bg-gray-300 dark:bg-gray-700 dark:hover:bg-gray-600
+                text-gray-700 dark:text-gray-200
+                font-semibold shadow-sm hover:shadow-md
+                disabled:opacity-50 disabled:cursor-not-allowed
+                transition-all
+              "
+            >
+              Play Again, Next Player First
+            </button>
+          </div>
+          {rematch.length > 0 && (
+            <p className="text-sm text-gray-600 dark:text-gray-400 mb-4">
+              {rematch.length} of {seats} want a rematch
+            </p>
+          )}
           <button
             onClick={handleCancelReconnect}
             className="
@@ -78,6 +158,32 @@ export default function Home() {
     return <GameBoard gameState={gameState} />;
   }
 
+  if (gameState !== null && typeof gameState === 'object' && 'Queued' in gameState) {
+    const { waiting, players } = gameState.Queued;
+    return (
+      <div className="min-h-screen flex items-center justify-center">
+        <div className="text-center">
+          <div className="text-4xl mb-4 animate-spin">⟳</div>
+          <p className="text-xl text-gray-600 dark:text-gray-400 mb-8">
+            Waiting for players ({waiting} of {players})
+          </p>
+          <button
+            onClick={handleCancelReconnect}
+            className="
+              px-6 py-3 rounded-lg
+              bg-gray-200 hover:bg-gray-300 dark:bg-gray-700 dark:hover:bg-gray-600
+              text-gray-700 dark:text-gray-200
+              font-semibold shadow-sm hover:shadow-md
+              transition-all
+            "
+          >
+            Leave Queue
+          </button>
+        </div>
+      </div>
+    );
+  }
+
   if (isJoining || connectionState === 'connecting') {
     return (
       <div className="min-h-screen flex items-center justify-center">
@@ -1,4 +1,5 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { MatchPreference } from "./MatchPreference";
 import type { RegicideAction } from "./RegicideAction";
 
-export type ClientMsg = { "Join": { lobby: string | null, client_token: string, } } | { "Action": { action: RegicideAction, } };
+export type ClientMsg = { "Join": { lobby: string | null, client_token: string, } } | { "Action": { action: RegicideAction, } } | "RequestHint" | { "Hello": { version: number, min_version: number, } } | { "QuickMatch": { client_token: string, preference: MatchPreference, } };
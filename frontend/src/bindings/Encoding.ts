@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * How frames on a game socket are encoded. Chosen when the socket opens, by
+ * `Sec-WebSocket-Protocol` or else the `encoding` query parameter.
+ */
+export type Encoding = "postcard" | "json";
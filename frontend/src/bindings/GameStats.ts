@@ -0,0 +1,28 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Running totals for one game.
+ */
+export type GameStats = { 
+/**
+ * Plays and yields; defending and picking after a jester are part of
+ * the turn they follow.
+ */
+turns: number, 
+/**
+ * Damage each player dealt, overkill included, in turn order.
+ */
+damage: Array<[string, number]>, 
+/**
+ * Cards hearts shuffled back from the discard pile into the library.
+ */
+healed: number, 
+/**
+ * Cards diamonds dealt out.
+ */
+drawn: number, 
+/**
+ * Enemies beaten with exactly their health, which sends them to the
+ * top of the library.
+ */
+exact_kills: number, jesters: number, };
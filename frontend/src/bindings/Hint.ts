@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { JsCard } from "./JsCard";
+import type { RegicideAction } from "./RegicideAction";
+
+/**
+ * A suggested move with the reasoning behind it.
+ */
+export type Hint = { action: RegicideAction, cards: Array<JsCard>, reasons: Array<string>, };
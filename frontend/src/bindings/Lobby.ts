@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * An open lobby as listed to players looking for a game.
+ */
+export type Lobby = { players: number, min_players: number, max_players: number, 
+/**
+ * House rule for jesters; `None` follows the player count.
+ */
+jesters: number | null, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A change to the list of open lobbies.
+ */
+export type LobbyEvent<L> = { "Created": { actor_id: string, lobby: L, } } | { "Updated": { actor_id: string, lobby: L, } } | { "Closed": { actor_id: string, } };
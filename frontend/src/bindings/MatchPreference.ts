@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What a player asks of a quick match. Players with equal preferences are
+ * seated together.
+ */
+export type MatchPreference = { players: number, 
+/**
+ * House rule for jesters; `None` follows the player count.
+ */
+jesters: number | null, };
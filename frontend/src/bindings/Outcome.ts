@@ -0,0 +1,21 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { GameStats } from "./GameStats";
+import type { JsCard } from "./JsCard";
+
+/**
+ * How a finished game went. Sent to the table and kept with the game in
+ * each player's history.
+ */
+export type Outcome = { victory: boolean, 
+/**
+ * Royals beaten, out of twelve.
+ */
+enemies_defeated: number, 
+/**
+ * The enemy the party fell to.
+ */
+defeated_by: JsCard | null, 
+/**
+ * Missing from games recorded before stats were kept.
+ */
+stats: GameStats, };
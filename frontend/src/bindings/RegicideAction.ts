@@ -1,3 +1,4 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Difficulty } from "./Difficulty";
 
-export type RegicideAction = "Init" | { "Play": { cards: Array<number>, } } | { "Discard": { cards: Array<number>, } } | { "Jester": { player: string, } };
+export type RegicideAction = "Init" | { "Play": { cards: Array<number>, } } | { "Discard": { cards: Array<number>, } } | { "Jester": { player: string, } } | { "AddBot": { difficulty: Difficulty, } } | { "RemoveBot": { player: string, } } | { "Rematch": { rotate: boolean, } };
@@ -1,5 +1,12 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Hint } from "./Hint";
 import type { JsCard } from "./JsCard";
+import type { Outcome } from "./Outcome";
 import type { Phase } from "./Phase";
 
-export type ServerMsg = { "Join": { joined: string, } } | { "Game": { phase: Phase, players: Array<[string, number]>, library_size: number, discard_size: number, damage: number, enemy: JsCard, hand: Array<JsCard>, resolving: Array<Array<JsCard>>, } } | "Victory" | "Defeat";
+export type ServerMsg = { "Join": { joined: string, } } | { "Game": { phase: Phase, players: Array<[string, number]>, 
+/**
+ * Round-trip milliseconds of connected players, in turn order.
+ * Bots and offline players are left out.
+ */
+latency: Array<[string, number]>, library_size: number, discard_size: number, damage: number, enemy: JsCard, hand: Array<JsCard>, resolving: Array<Array<JsCard>>, } } | { "Victory": { rematch: Array<string>, seats: number, } } | { "Defeat": { rematch: Array<string>, seats: number, } } | { "ServerShutdown": { reconnect_after: number, } } | { "Hint": { hints: Array<Hint>, } } | { "IncompatibleVersion": { version: number, min_version: number, } } | { "Queued": { position: number, waiting: number, players: number, } } | { "Finished": { outcome: Outcome, rematch: Array<string>, seats: number, } };
@@ -40,6 +40,10 @@ export function ActionButtons({
     onAction('Init');
   };
 
+  const handleAddBot = () => {
+    onAction({ AddBot: { difficulty: 'Normal' } });
+  };
+
   const handlePlay = () => {
     if (canPlay) {
       onAction({
@@ -77,6 +81,21 @@ export function ActionButtons({
         </button>
       )}
 
+      {!gameStarted && (
+        <button
+          onClick={handleAddBot}
+          className="
+            px-6 py-2.5 rounded-lg
+            bg-sky-400 hover:bg-sky-500 dark:bg-sky-300 dark:hover:bg-sky-400
+            text-gray-800 dark:text-gray-900
+            font-semibold shadow-sm hover:shadow-md
+            transition-all
+          "
+        >
+          Add Bot
+        </button>
+      )}
+
       {gameStarted && (
         <>
           <button
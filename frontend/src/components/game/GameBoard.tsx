@@ -10,9 +10,11 @@ import { PlayerList } from './PlayerList';
 import { PlayerHand } from './PlayerHand';
 import { PhaseIndicator } from './PhaseIndicator';
 import { ActionButtons } from './ActionButtons';
+import { HintPanel } from './HintPanel';
 import { useWebSocket } from '@/contexts/WebSocketContext';
 import { useClient } from '@/contexts/ClientContext';
-import { createActionMessage } from '@/lib/websocket';
+import { useGame } from '@/contexts/GameContext';
+import { createActionMessage, createHintRequest } from '@/lib/websocket';
 
 interface GameBoardProps {
   gameState: Extract<ServerMsg, { Game: unknown }>;
@@ -21,10 +23,11 @@ interface GameBoardProps {
 export function GameBoard({ gameState }: GameBoardProps) {
   const { sendMessage, connectionState, retryAttempt } = useWebSocket();
   const { clientToken } = useClient();
+  const { hints, setHints } = useGame();
   const [selectedIndices, setSelectedIndices] = useState<number[]>([]);
   const [showResolvingModal, setShowResolvingModal] = useState(false);
 
-  const { phase, players, library_size, discard_size, damage, enemy, hand, resolving } =
+  const { phase, players, latency, library_size, discard_size, damage, enemy, hand, resolving } =
     gameState.Game;
 
   const gameStarted = typeof phase !== 'string' || phase === 'Victory' || phase === 'Defeat';
@@ -40,9 +43,14 @@ export function GameBoard({ gameState }: GameBoardProps) {
     const success = sendMessage(msg);
     if (success) {
       setSelectedIndices([]);
+      setHints(null);
     }
   };
 
+  const handleRequestHint = () => {
+    sendMessage(createHintRequest());
+  };
+
   const handlePlayerClick = (playerId: string) => {
     const action: RegicideAction = {
       Jester: {
@@ -77,6 +85,7 @@ export function GameBoard({ gameState }: GameBoardProps) {
           </div>
           <PlayerList
             players={players}
+            latency={latency}
             currentPlayer={currentPlayer || undefined}
             selfId={clientToken || ''}
             phase={phase}
@@ -102,6 +111,8 @@ export function GameBoard({ gameState }: GameBoardProps) {
           <div className="md:hidden">
             <PlayerList
               players={players}
+            latency={latency}
+              latency={latency}
               currentPlayer={currentPlayer || undefined}
               selfId={clientToken || ''}
               phase={phase}
@@ -125,6 +136,15 @@ export function GameBoard({ gameState }: GameBoardProps) {
               onAction={handleAction}
               gameStarted={gameStarted}
             />
+            {gameStarted && currentPlayer === clientToken && (
+              <button
+                onClick={handleRequestHint}
+                className="text-sm underline text-gray-600 hover:text-gray-800 dark:text-gray-400 dark:hover:text-gray-200"
+              >
+                Suggest a move
+              </button>
+            )}
+            {hints && <HintPanel hints={hints} onClose={() => setHints(null)} />}
           </div>
         </div>
       </div>
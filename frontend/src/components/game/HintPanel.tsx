@@ -0,0 +1,45 @@
+import type { Hint } from '@/bindings/Hint';
+import { getSuitSymbol } from '@/lib/cardUtils';
+
+interface HintPanelProps {
+  hints: Hint[];
+  onClose: () => void;
+}
+
+export function HintPanel({ hints, onClose }: HintPanelProps) {
+  return (
+    <div className="w-full max-w-xl rounded-lg bg-white/80 dark:bg-gray-800/80 shadow-sm p-4">
+      <div className="flex items-center justify-between mb-2">
+        <h2 className="font-semibold">Suggestions</h2>
+        <button
+          onClick={onClose}
+          className="text-sm text-gray-500 hover:text-gray-700 dark:hover:text-gray-300"
+        >
+          Dismiss
+        </button>
+      </div>
+      {hints.length === 0 ? (
+        <p className="text-sm text-gray-600 dark:text-gray-400">Nothing to suggest right now.</p>
+      ) : (
+        <ol className="flex flex-col gap-2">
+          {hints.map((hint, i) => (
+            <li key={i} className="text-sm">
+              <span className="font-semibold">
+                {hint.cards.length > 0
+                  ? hint.cards.map((card) => `${card.value}${getSuitSymbol(card.suit)}`).join(' ')
+                  : typeof hint.action === 'object' && 'Jester' in hint.action
+                  ? `Pass to ${hint.action.Jester.player}`
+                  : 'No cards'}
+              </span>
+              <ul className="list-disc list-inside text-gray-600 dark:text-gray-400">
+                {hint.reasons.map((reason, j) => (
+                  <li key={j}>{reason}</li>
+                ))}
+              </ul>
+            </li>
+          ))}
+        </ol>
+      )}
+    </div>
+  );
+}
@@ -1,15 +1,27 @@
 import type { Phase } from '@/bindings/Phase';
 import { CardBack } from '@/components/shared/CardBack';
 
+// Round trips above this are flagged as lagging.
+const LAG_MS = 300;
+
 interface PlayerListProps {
   players: Array<[string, number]>;
+  latency: Array<[string, number]>;
   currentPlayer?: string;
   selfId: string;
   phase: Phase;
   onPlayerClick?: (playerId: string) => void;
 }
 
-export function PlayerList({ players, currentPlayer, selfId, phase, onPlayerClick }: PlayerListProps) {
+export function PlayerList({
+  players,
+  latency,
+  currentPlayer,
+  selfId,
+  phase,
+  onPlayerClick,
+}: PlayerListProps) {
+  const latencyById = new Map(latency);
   const isJesterPhase = typeof phase === 'object' && 'Jester' in phase;
   const jesterPlayerId = isJesterPhase ? phase.Jester : null;
   const canSelectPlayers = isJesterPhase && jesterPlayerId === selfId;
@@ -30,6 +42,7 @@ export function PlayerList({ players, currentPlayer, selfId, phase, onPlayerClic
           const isCurrentPlayer = playerId === currentPlayer;
           const isSelf = playerId === selfId;
           const isClickable = canSelectPlayers && playerId !== selfId;
+          const rtt = latencyById.get(playerId);
 
           return (
             <div
@@ -53,6 +66,15 @@ export function PlayerList({ players, currentPlayer, selfId, phase, onPlayerClic
                 <div className="font-medium text-gray-800 dark:text-gray-100">
                   {playerId} {isSelf && '(You)'}
                 </div>
+                {rtt !== undefined && (
+                  <div
+                    className={`text-xs ${
+                      rtt > LAG_MS ? 'text-red-500 font-semibold' : 'text-gray-500 dark:text-gray-400'
+                    }`}
+                  >
+                    {rtt > LAG_MS ? `Lagging · ${rtt}ms` : `${rtt}ms`}
+                  </div>
+                )}
               </div>
               <div className="flex-shrink-0">
                 <CardBack count={handCount} />
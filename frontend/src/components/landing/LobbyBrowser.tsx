@@ -12,14 +12,22 @@ export function LobbyBrowser({ onJoinSuccess }: LobbyBrowserProps) {
   const { connect } = useWebSocket();
   const { clientToken, setLobbyId } = useClient();
   const [selectedLobby, setSelectedLobby] = useState<string | null>(null);
+  const [matchPlayers, setMatchPlayers] = useState(2);
 
   const handleRowClick = (lobbyId: string) => {
     setSelectedLobby((prev) => (prev === lobbyId ? null : lobbyId));
   };
 
-  const handleJoinGame = () => {
-    if (!clientToken || clientToken.length === 0 || clientToken.length > 32) {
+  const validToken = (token: string | null): token is string => {
+    if (!token || token.length === 0 || token.length > 32) {
       alert('Please enter a valid client token (1-32 characters)');
+      return false;
+    }
+    return true;
+  };
+
+  const handleJoinGame = () => {
+    if (!validToken(clientToken)) {
       return;
     }
 
@@ -29,6 +37,16 @@ export function LobbyBrowser({ onJoinSuccess }: LobbyBrowserProps) {
     onJoinSuccess();
   };
 
+  const handleQuickMatch = () => {
+    if (!validToken(clientToken)) {
+      return;
+    }
+
+    setLobbyId(null);
+    connect(clientToken, null, { players: matchPlayers, jesters: null });
+    onJoinSuccess();
+  };
+
   const buttonText = selectedLobby ? 'Join Game' : 'New Game';
 
   return (
@@ -81,7 +99,7 @@ export function LobbyBrowser({ onJoinSuccess }: LobbyBrowserProps) {
                 </td>
               </tr>
             ) : (
-              lobbies.map(([lobbyId, count]) => (
+              lobbies.map(([lobbyId, lobby]) => (
                 <tr
                   key={lobbyId}
                   onClick={() => handleRowClick(lobbyId)}
@@ -98,7 +116,7 @@ export function LobbyBrowser({ onJoinSuccess }: LobbyBrowserProps) {
                     {lobbyId}
                   </td>
                   <td className="px-4 py-3 text-sm text-gray-600 dark:text-gray-400">
-										{count}
+										{lobby.players}/{lobby.max_players}
                   </td>
                 </tr>
               ))
@@ -121,6 +139,38 @@ export function LobbyBrowser({ onJoinSuccess }: LobbyBrowserProps) {
       >
         {buttonText}
       </button>
+
+      <div className="mt-3 flex gap-2">
+        <select
+          value={matchPlayers}
+          onChange={(e) => setMatchPlayers(Number(e.target.value))}
+          className="
+            px-3 py-3 rounded-lg text-sm
+            bg-gray-200 dark:bg-gray-700
+            text-gray-700 dark:text-gray-200
+          "
+        >
+          {[2, 3, 4].map((n) => (
+            <option key={n} value={n}>
+              {n} players
+            </option>
+          ))}
+        </select>
+        <button
+          onClick={handleQuickMatch}
+          disabled={!clientToken}
+          className="
+            flex-1 px-6 py-3 rounded-lg
+            bg-gray-200 hover:bg-gray-300 dark:bg-gray-700 dark:hover:bg-gray-600
+            text-gray-700 dark:text-gray-200
+            font-semibold shadow-sm hover:shadow-md
+            disabled:opacity-50 disabled:cursor-not-allowed
+            transition-all
+          "
+        >
+          Quick Match
+        </button>
+      </div>
     </div>
   );
 }
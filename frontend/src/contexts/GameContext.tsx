@@ -2,10 +2,13 @@
 
 import { createContext, useContext, useState, type ReactNode } from 'react';
 import type { ServerMsg } from '@/bindings/ServerMsg';
+import type { Hint } from '@/bindings/Hint';
 
 interface GameContextValue {
   gameState: ServerMsg | null;
   setGameState: (state: ServerMsg | null) => void;
+  hints: Hint[] | null;
+  setHints: (hints: Hint[] | null) => void;
   isInGame: boolean;
 }
 
@@ -13,15 +16,18 @@ const GameContext = createContext<GameContextValue | null>(null);
 
 export function GameProvider({ children }: { children: ReactNode }) {
   const [gameState, setGameState] = useState<ServerMsg | null>(null);
+  const [hints, setHints] = useState<Hint[] | null>(null);
 
   const isInGame =
     gameState !== null &&
-    (gameState === 'Victory' ||
-      gameState === 'Defeat' ||
-      (typeof gameState === 'object' && 'Game' in gameState));
+    typeof gameState === 'object' &&
+    ('Finished' in gameState ||
+      'Victory' in gameState ||
+      'Defeat' in gameState ||
+      'Game' in gameState);
 
   return (
-    <GameContext.Provider value={{ gameState, setGameState, isInGame }}>
+    <GameContext.Provider value={{ gameState, setGameState, hints, setHints, isInGame }}>
       {children}
     </GameContext.Provider>
   );
@@ -3,8 +3,14 @@
 import { createContext, useContext, useState, useEffect, useRef, useCallback, type ReactNode } from 'react';
 import type { ClientMsg } from '@/bindings/ClientMsg';
 import type { ServerMsg } from '@/bindings/ServerMsg';
-import { createJoinMessage, sendWebSocketMessage } from '@/lib/websocket';
-import { initWasm, deserializeMessage } from '@/lib/parse';
+import type { MatchPreference } from '@/bindings/MatchPreference';
+import {
+  createHelloMessage,
+  createJoinMessage,
+  createQuickMatchMessage,
+  sendWebSocketMessage,
+} from '@/lib/websocket';
+import { initWasm, deserializeMessage, protocolVersion } from '@/lib/parse';
 
 export type ConnectionState = 'disconnected' | 'connecting' | 'connected' | 'reconnecting';
 
@@ -14,7 +20,8 @@ interface WebSocketContextValue {
   retryAttempt: number;
   nextRetryIn: number;
   sendMessage: (msg: ClientMsg) => boolean;
-  connect: (token: string, lobbyId: string | null) => void;
+  /** Joins `lobbyId`, or a new lobby; with a preference, queues for a quick match instead. */
+  connect: (token: string, lobbyId: string | null, quickMatch?: MatchPreference) => void;
   disconnect: () => void;
   reconnect: () => void;
 }
@@ -24,6 +31,9 @@ const WebSocketContext = createContext<WebSocketContextValue | null>(null);
 const MAX_RETRY_ATTEMPTS = 10;
 const BASE_DELAY = 1000;
 const MAX_DELAY = 32000;
+// Set before reloading for a newer bundle, so a server that keeps rejecting
+// us cannot send the page into a reload loop.
+const VERSION_RELOAD_KEY = 'regicide-version-reload';
 
 export function WebSocketProvider({
   children,
@@ -42,6 +52,7 @@ export function WebSocketProvider({
   const retryIntervalRef = useRef<NodeJS.Timeout | null>(null);
   const currentTokenRef = useRef<string | null>(null);
   const currentLobbyIdRef = useRef<string | null>(null);
+  const currentQuickMatchRef = useRef<MatchPreference | undefined>(undefined);
   const shouldReconnectRef = useRef(false);
 
   const clearRetryTimers = useCallback(() => {
@@ -69,11 +80,12 @@ export function WebSocketProvider({
   }, [clearRetryTimers]);
 
   const connect = useCallback(
-    (token: string, lobbyId: string | null) => {
+    (token: string, lobbyId: string | null, quickMatch?: MatchPreference) => {
       disconnect();
 
       currentTokenRef.current = token;
       currentLobbyIdRef.current = lobbyId;
+      currentQuickMatchRef.current = quickMatch;
       shouldReconnectRef.current = true;
       setConnectionState('connecting');
       setRetryAttempt(0);
@@ -90,7 +102,12 @@ export function WebSocketProvider({
           setNextRetryIn(0);
           clearRetryTimers();
 
-          const joinMsg = createJoinMessage(token, lobbyId);
+          sendWebSocketMessage(websocket, createHelloMessage());
+          // Once matched the server seats us at our table again, so asking
+          // for the same quick match is also how we reconnect to it.
+          const joinMsg = quickMatch
+            ? createQuickMatchMessage(token, quickMatch)
+            : createJoinMessage(token, lobbyId);
           sendWebSocketMessage(websocket, joinMsg);
         };
 
@@ -98,6 +115,20 @@ export function WebSocketProvider({
           try {
             const bytes = new Uint8Array(event.data);
             const msg = deserializeMessage(bytes) as ServerMsg;
+            if (typeof msg === 'object' && 'IncompatibleVersion' in msg) {
+              // Retrying with the same bundle cannot succeed.
+              shouldReconnectRef.current = false;
+              const server = msg.IncompatibleVersion;
+              const stale = server.min_version > protocolVersion().version;
+              if (stale && !sessionStorage.getItem(VERSION_RELOAD_KEY)) {
+                sessionStorage.setItem(VERSION_RELOAD_KEY, String(server.version));
+                window.location.reload();
+              } else {
+                console.error('Server protocol is incompatible with this client:', server);
+              }
+            } else if (typeof msg === 'object' && 'Join' in msg) {
+              sessionStorage.removeItem(VERSION_RELOAD_KEY);
+            }
             onMessage(msg);
           } catch (error) {
             console.error('Failed to parse WebSocket message:', error);
@@ -125,7 +156,7 @@ export function WebSocketProvider({
               clearRetryTimers();
               setRetryAttempt((prev) => prev + 1);
               if (currentTokenRef.current) {
-                connect(currentTokenRef.current, currentLobbyIdRef.current);
+                connect(currentTokenRef.current, currentLobbyIdRef.current, currentQuickMatchRef.current);
               }
             }, delay);
           } else if (shouldReconnectRef.current) {
@@ -148,7 +179,7 @@ export function WebSocketProvider({
     if (currentTokenRef.current) {
       clearRetryTimers();
       setRetryAttempt(0);
-      connect(currentTokenRef.current, currentLobbyIdRef.current);
+      connect(currentTokenRef.current, currentLobbyIdRef.current, currentQuickMatchRef.current);
     }
   }, [connect, clearRetryTimers]);
 
@@ -1,53 +1,83 @@
-import { useState, useEffect, useCallback, useRef } from 'react';
+import { useState, useEffect, useCallback } from 'react';
+import type { Lobby } from '@/bindings/Lobby';
+import type { LobbyEvent } from '@/bindings/LobbyEvent';
 
-const LOBBIES_ENDPOINT = '/lobbies';
-const REFRESH_INTERVAL = 5000;
+const LOBBY_EVENTS_ENDPOINT = '/lobbies/events';
 
-type Lobby = [string, number];
+export type LobbyEntry = [string, Lobby];
 
-export function useLobbies(autoRefresh: boolean = true) {
-  const [lobbies, setLobbies] = useState<Lobby[]>([]);
+/** Which lobbies to list; unset fields match any. */
+export interface LobbyFilter {
+  openSeats?: number;
+  maxPlayers?: number;
+  jesters?: number;
+}
+
+function eventsUrl(filter: LobbyFilter): string {
+  const params = new URLSearchParams();
+  if (filter.openSeats !== undefined) params.set('open_seats', String(filter.openSeats));
+  if (filter.maxPlayers !== undefined) params.set('max_players', String(filter.maxPlayers));
+  if (filter.jesters !== undefined) params.set('jesters', String(filter.jesters));
+  const query = params.toString();
+  return query ? `${LOBBY_EVENTS_ENDPOINT}?${query}` : LOBBY_EVENTS_ENDPOINT;
+}
+
+function applyEvent(lobbies: Map<string, Lobby>, event: LobbyEvent<Lobby>): Map<string, Lobby> {
+  const next = new Map(lobbies);
+  if ('Created' in event) {
+    next.set(event.Created.actor_id, event.Created.lobby);
+  } else if ('Updated' in event) {
+    next.set(event.Updated.actor_id, event.Updated.lobby);
+  } else {
+    next.delete(event.Closed.actor_id);
+  }
+  return next;
+}
+
+/**
+ * Open lobbies, kept current by the server pushing changes as they happen.
+ * The server sends every matching lobby when the stream (re)opens, so the
+ * list is rebuilt from scratch on each connection.
+ */
+export function useLobbies(live: boolean = true, filter: LobbyFilter = {}) {
+  const [lobbies, setLobbies] = useState<Map<string, Lobby>>(new Map());
   const [isLoading, setIsLoading] = useState(false);
   const [error, setError] = useState<string | null>(null);
-  const intervalRef = useRef<NodeJS.Timeout | null>(null);
+  const [generation, setGeneration] = useState(0);
+  const url = eventsUrl(filter);
 
-  const fetchLobbies = useCallback(async () => {
+  useEffect(() => {
+    if (!live) return;
     setIsLoading(true);
     setError(null);
 
-    try {
-      const response = await fetch(LOBBIES_ENDPOINT);
-      if (!response.ok) {
-        throw new Error(`Failed to fetch lobbies: ${response.statusText}`);
-      }
-      const data: Lobby[] = await response.json();
-      setLobbies(data);
-    } catch (err) {
-      const message = err instanceof Error ? err.message : 'Unknown error';
-      setError(message);
-      console.error('Error fetching lobbies:', err);
-    } finally {
+    const source = new EventSource(url);
+    source.onopen = () => {
+      setLobbies(new Map());
       setIsLoading(false);
-    }
-  }, []);
-
-  useEffect(() => {
-    fetchLobbies();
-
-    if (autoRefresh) {
-      intervalRef.current = setInterval(fetchLobbies, REFRESH_INTERVAL);
-    }
+      setError(null);
+    };
+    source.onmessage = (message) => {
+      try {
+        const event: LobbyEvent<Lobby> = JSON.parse(message.data);
+        setLobbies((prev) => applyEvent(prev, event));
+      } catch (err) {
+        console.error('Error parsing lobby event:', err);
+      }
+    };
+    source.onerror = () => {
+      // EventSource retries on its own; say so until it is back.
+      setError('Lost connection to the lobby list, reconnecting...');
+    };
 
     return () => {
-      if (intervalRef.current) {
-        clearInterval(intervalRef.current);
-      }
+      source.close();
     };
-  }, [fetchLobbies, autoRefresh]);
+  }, [url, live, generation]);
 
   const refresh = useCallback(() => {
-    fetchLobbies();
-  }, [fetchLobbies]);
+    setGeneration((g) => g + 1);
+  }, []);
 
-  return { lobbies, isLoading, error, refresh };
+  return { lobbies: Array.from(lobbies.entries()) as LobbyEntry[], isLoading, error, refresh };
 }
@@ -1,4 +1,11 @@
-import init, { deserialize_payload, serialize_payload } from '../../pkg/parse';
+import init, {
+  deserialize_json,
+  deserialize_payload,
+  min_protocol_version,
+  protocol_version,
+  serialize_json,
+  serialize_payload,
+} from '../../pkg/parse';
 
 let wasmInitialized = false;
 let wasmInitPromise: Promise<void> | null = null;
@@ -39,6 +46,28 @@ export function deserializeMessage(bytes: Uint8Array): unknown {
   return deserialize_payload(bytes);
 }
 
+export function serializeJson(value: unknown): string {
+  if (!wasmInitialized) {
+    throw new Error('WASM module not initialized. Call initWasm() first.');
+  }
+  return serialize_json(value);
+}
+
+export function deserializeJson(text: string): unknown {
+  if (!wasmInitialized) {
+    throw new Error('WASM module not initialized. Call initWasm() first.');
+  }
+  return deserialize_json(text);
+}
+
+/** The protocol revision this bundle speaks and the oldest it accepts. */
+export function protocolVersion(): { version: number; min_version: number } {
+  if (!wasmInitialized) {
+    throw new Error('WASM module not initialized. Call initWasm() first.');
+  }
+  return { version: protocol_version(), min_version: min_protocol_version() };
+}
+
 export function isWasmInitialized(): boolean {
   return wasmInitialized;
 }
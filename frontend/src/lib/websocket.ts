@@ -1,6 +1,11 @@
 import type { ClientMsg } from '@/bindings/ClientMsg';
+import type { MatchPreference } from '@/bindings/MatchPreference';
 import type { RegicideAction } from '@/bindings/RegicideAction';
-import { serializeMessage } from './parse';
+import { protocolVersion, serializeMessage } from './parse';
+
+export function createHelloMessage(): ClientMsg {
+  return { Hello: protocolVersion() };
+}
 
 export function createJoinMessage(clientToken: string, lobbyId: string | null): ClientMsg {
   return {
@@ -11,6 +16,15 @@ export function createJoinMessage(clientToken: string, lobbyId: string | null):
   };
 }
 
+export function createQuickMatchMessage(clientToken: string, preference: MatchPreference): ClientMsg {
+  return {
+    QuickMatch: {
+      client_token: clientToken,
+      preference,
+    },
+  };
+}
+
 export function createActionMessage(action: RegicideAction): ClientMsg {
   return {
     Action: {
@@ -19,6 +33,10 @@ export function createActionMessage(action: RegicideAction): ClientMsg {
   };
 }
 
+export function createHintRequest(): ClientMsg {
+  return 'RequestHint';
+}
+
 export function sendWebSocketMessage(ws: WebSocket | null, msg: ClientMsg): boolean {
   if (!ws || ws.readyState !== WebSocket.OPEN) {
     return false;
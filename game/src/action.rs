@@ -1,19 +1,21 @@
-use crate::{Combo, JsCard, Phase, ServerMsg, SharedState, Suit, UserState};
+use crate::{
+    Combo, Difficulty, GameState, JsCard, Lobby, MAX_PLAYERS, MatchPreference, Outcome, Phase,
+    Ruleset, ServerMsg, SharedState, Suit, UserState, View, bot_actions, bot_id, hints, is_bot,
+};
 use actor::{Action, ActorId, UserId};
 use arrayvec::ArrayVec;
-use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::SliceRandom};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, fmt::Debug};
+use std::{collections::HashMap, fmt::Debug, time::Duration};
 use ts_rs::TS;
 
-const MAX_PLAYERS: usize = 4;
 const HAND_SIZE: usize = 9;
-const JESTERS: usize = 2;
 
 #[derive(Clone, Debug, Serialize, Deserialize, TS)]
 #[ts(export, export_to = "../../frontend/src/bindings/")]
 pub enum RegicideAction {
     Init,
+    /// Hand indices of the combo to play. An empty play yields to the enemy.
     Play {
         #[ts(as = "Vec<u8>")]
         cards: ArrayVec<u8, 4>,
@@ -26,14 +28,48 @@ pub enum RegicideAction {
         #[ts(as = "String")]
         player: UserId,
     },
+    /// Host only, before the game starts.
+    AddBot {
+        difficulty: Difficulty,
+    },
+    /// Host only, before the game starts.
+    RemoveBot {
+        #[ts(as = "String")]
+        player: UserId,
+    },
+    /// Once the game is over, vote to play again at the same table. The next
+    /// game is dealt when every player still seated has voted; it keeps the
+    /// seating and moves first turn along only if every vote asks to.
+    Rematch {
+        rotate: bool,
+    },
 }
 
 impl Action for RegicideAction {
     type Shared = SharedState;
     type User = UserState;
     type Msg = ServerMsg;
+    type Summary = Outcome;
+    type Rules = Ruleset;
+    type Listing = Lobby;
+    type Preference = MatchPreference;
     fn can_join(shared: &Self::Shared, user: &HashMap<UserId, Self::User>) -> bool {
-        matches!(shared, SharedState::Uninit) && user.len() < MAX_PLAYERS
+        matches!(shared.game, GameState::Uninit) && user.len() < shared.rules.max_players as usize
+    }
+    fn listing(shared: &Self::Shared, user: &HashMap<UserId, Self::User>) -> Lobby {
+        Lobby {
+            players: user.len() as u8,
+            min_players: shared.rules.min_players,
+            max_players: shared.rules.max_players,
+            jesters: shared.rules.jesters,
+        }
+    }
+    fn match_rules(rules: &Ruleset, preference: &MatchPreference) -> Option<(Ruleset, usize)> {
+        let rules = rules.for_match(preference).ok()?;
+        Some((rules, preference.players as usize))
+    }
+    fn shared(rules: &Ruleset) -> SharedState {
+        SharedState::new(*rules)
     }
     fn update(
         self,
@@ -41,33 +77,27 @@ impl Action for RegicideAction {
         user: &mut HashMap<UserId, Self::User>,
         user_id: UserId,
     ) {
-        match (self, shared) {
-            (Self::Init, shared) if matches!(shared, SharedState::Uninit) => {
-                let mut rng = rand::rng();
-                let hand_size = HAND_SIZE - user.len();
-                let jesters = user.len().saturating_sub(JESTERS);
-                let mut turn_order: Vec<UserId> = user.keys().copied().collect();
-                turn_order.shuffle(&mut rng);
-                if let Ok(turn_order) = turn_order.try_into() {
-                    shared.init(jesters, turn_order, rng);
-                    if let SharedState::Init { deck, .. } = shared {
-                        user.values_mut().for_each(|u| {
-                            let mut hand = ArrayVec::new();
-                            for _ in 0..hand_size {
-                                hand.push(deck.draw());
-                            }
-                            *u = UserState::Init { hand };
-                        });
-                    }
-                }
+        let rules = &shared.rules;
+        match (self, &mut shared.game) {
+            (Self::Init, game @ GameState::Uninit) if user.len() >= rules.min_players as usize => {
+                let seed = seed(rules, 0);
+                let mut turn_order: ArrayVec<UserId, MAX_PLAYERS> =
+                    user.keys().copied().take(MAX_PLAYERS).collect();
+                // Map iteration order is random; sort so a seed fixes the seating.
+                turn_order.sort();
+                turn_order.shuffle(&mut StdRng::seed_from_u64(seed));
+                deal(game, rules, user, turn_order, seed, 0);
             }
             (
                 Self::Play { cards },
-                SharedState::Init {
+                GameState::Init {
                     phase,
                     deck,
                     turn_order,
                     damage,
+                    yields,
+                    stats,
+                    ..
                 },
             ) if matches!(*phase, Phase::Play(id) if id == user_id) => {
                 let Some(player) = user.get_mut(&user_id) else {
@@ -76,15 +106,29 @@ impl Action for RegicideAction {
                 let Some(&enemy) = deck.battling() else {
                     return;
                 };
+                if cards.is_empty() {
+                    // Everyone else yielding in a row rules out one more yield.
+                    if (*yields as usize) + 1 < turn_order.len() {
+                        *yields += 1;
+                        stats.turns += 1;
+                        *phase = Phase::Defend(user_id);
+                    } else if matches!(player, UserState::Init { hand } if hand.is_empty()) {
+                        *phase = Phase::Defeat;
+                    }
+                    return;
+                }
                 let cards = player.cards(cards);
                 let Some(combo) = Combo::from_cards(cards) else {
                     return;
                 };
+                *yields = 0;
+                stats.turns += 1;
                 deck.play_cards(combo);
                 if matches!(combo, Combo::Jester) {
+                    stats.jesters += 1;
                     *phase = Phase::Jester(user_id);
                 } else {
-                    deck.heal(combo.suit_value(Suit::Heart) as usize);
+                    stats.healed += deck.heal(combo.suit_value(Suit::Heart) as usize) as u16;
 
                     let mut draw = combo.suit_value(Suit::Diamond);
                     let mut players = turn_order.iter().cycle().peekable();
@@ -96,31 +140,41 @@ impl Action for RegicideAction {
                         let player = players.next().unwrap();
                         if let Some(UserState::Init { hand }) = user.get_mut(player)
                             && hand.len() < max_hand_size
+                            && let Some(card) = deck.draw()
                         {
-                            hand.push(deck.draw());
+                            hand.push(card);
+                            stats.drawn += 1;
                         }
                         draw -= 1;
                     }
 
                     *damage += combo.strength();
+                    stats.dealt(user_id, combo.strength());
                     if *damage >= enemy.value() * 2 {
-                        deck.next_battle(*damage == enemy.value() * 2);
+                        let exact = *damage == enemy.value() * 2;
+                        stats.exact_kills += exact as u8;
+                        deck.next_battle(exact);
+                        *damage = 0;
+                        // The killer starts the next fight; otherwise the enemy strikes back.
                         if deck.battling().is_none() {
                             *phase = Phase::Victory;
                         } else {
-                            *phase = Phase::Defend(user_id);
+                            *phase = Phase::Play(user_id);
                         }
+                    } else {
+                        *phase = Phase::Defend(user_id);
                     }
                 }
             }
-            (Self::Jester { player }, SharedState::Init { phase, .. }) if matches!(*phase, Phase::Jester(id) if id == user_id) => {
-                if user.contains_key(&player) {
-                    *phase = Phase::Play(player);
-                }
+            (Self::Jester { player }, GameState::Init { phase, .. })
+                if matches!(*phase, Phase::Jester(id) if id == user_id)
+                    && user.contains_key(&player) =>
+            {
+                *phase = Phase::Play(player);
             }
             (
                 Self::Discard { cards },
-                SharedState::Init {
+                GameState::Init {
                     phase,
                     deck,
                     turn_order,
@@ -149,28 +203,89 @@ impl Action for RegicideAction {
                     *phase = Phase::Defeat;
                 }
             }
+            (Self::AddBot { difficulty }, GameState::Uninit)
+                if shared.host == Some(user_id)
+                    && user.len() < rules.max_players as usize
+                    && !shared.bots.is_full() =>
+            {
+                let bot = bot_id(&mut rand::rng());
+                shared.bots.push((bot, difficulty));
+                user.insert(bot, UserState::default());
+            }
+            (Self::RemoveBot { player }, GameState::Uninit)
+                if shared.host == Some(user_id) && is_bot(&shared.bots, &player) =>
+            {
+                shared.bots.retain(|(id, _)| *id != player);
+                user.remove(&player);
+            }
+            (
+                Self::Rematch { rotate },
+                GameState::Init {
+                    phase: Phase::Victory | Phase::Defeat,
+                    rematch,
+                    ..
+                },
+            ) if user.contains_key(&user_id) => {
+                rematch.retain(|(id, _)| *id != user_id);
+                rematch.push((user_id, rotate));
+                start_rematch(shared, user);
+            }
             _ => (),
         };
     }
     fn join_msg(actor_id: ActorId) -> ServerMsg {
         ServerMsg::Join { joined: actor_id }
     }
+    fn shutdown_msg(reconnect_after: Duration) -> ServerMsg {
+        ServerMsg::ServerShutdown {
+            reconnect_after: reconnect_after.as_secs() as u32,
+        }
+    }
+    fn incompatible_msg(version: u16, min_version: u16) -> ServerMsg {
+        ServerMsg::IncompatibleVersion {
+            version,
+            min_version,
+        }
+    }
+    fn queued_msg(position: usize, waiting: usize, players: usize) -> ServerMsg {
+        ServerMsg::Queued {
+            position: position as u8,
+            waiting: waiting as u8,
+            players: players as u8,
+        }
+    }
+    fn downgrade(msg: ServerMsg, version: u16) -> ServerMsg {
+        msg.downgrade(version)
+    }
     fn msg(shared: &SharedState, user: &HashMap<UserId, UserState>) -> Vec<(UserId, ServerMsg)> {
         user.keys()
-            .filter_map(|user_id| match shared {
-                SharedState::Init {
-                    phase: Phase::Victory,
-                    ..
-                } => Some((*user_id, ServerMsg::Victory)),
-                SharedState::Init {
-                    phase: Phase::Defeat,
+            .filter_map(|user_id| match &shared.game {
+                GameState::Init {
+                    phase: Phase::Victory | Phase::Defeat,
+                    rematch,
                     ..
-                } => Some((*user_id, ServerMsg::Defeat)),
-                SharedState::Init {
+                } => {
+                    let outcome = Self::summary(shared, user)?;
+                    let rematch = voters(shared, user)
+                        .filter(|id| rematch.iter().any(|(voter, _)| voter == *id))
+                        .copied()
+                        .collect();
+                    let seats = voters(shared, user).count() as u8;
+                    Some((
+                        *user_id,
+                        ServerMsg::Finished {
+                            outcome,
+                            rematch,
+                            seats,
+                        },
+                    ))
+                }
+                GameState::Init {
                     phase,
                     deck,
                     turn_order,
                     damage,
+                    ..
                 } => {
                     let players = turn_order
                         .iter()
@@ -181,6 +296,10 @@ impl Action for RegicideAction {
                             })
                         })
                         .collect();
+                    let latency = turn_order
+                        .iter()
+                        .filter_map(|id| Some((*id, *shared.latency.get(id)?)))
+                        .collect();
                     let library_size = deck.library_count() as u8;
                     let discard_size = deck.discard_count() as u8;
                     let enemy = deck.battling()?.into();
@@ -194,6 +313,7 @@ impl Action for RegicideAction {
                         ServerMsg::Game {
                             phase: *phase,
                             players,
+                            latency,
                             library_size,
                             discard_size,
                             damage: *damage,
@@ -207,4 +327,514 @@ impl Action for RegicideAction {
             })
             .collect()
     }
+    fn joined(shared: &mut SharedState, user_id: UserId) {
+        shared.host.get_or_insert(user_id);
+    }
+    fn connected(
+        shared: &mut SharedState,
+        user: &mut HashMap<UserId, UserState>,
+        user_id: UserId,
+        connected: bool,
+    ) {
+        if connected {
+            shared.connected.insert(user_id);
+        } else {
+            shared.connected.remove(&user_id);
+            // The others may only have been waiting on them to vote.
+            start_rematch(shared, user);
+        }
+    }
+    fn bots(
+        shared: &SharedState,
+        user: &HashMap<UserId, UserState>,
+    ) -> Vec<(UserId, RegicideAction)> {
+        bot_actions(shared, user)
+    }
+    fn latency(shared: &mut SharedState, user_id: UserId, rtt: Option<Duration>) {
+        match rtt {
+            Some(rtt) => {
+                let ms = rtt.as_millis().min(u32::MAX as u128) as u32;
+                shared.latency.insert(user_id, ms);
+            }
+            None => {
+                shared.latency.remove(&user_id);
+            }
+        }
+    }
+    fn leave(shared: &mut SharedState, user: &mut HashMap<UserId, UserState>, user_id: UserId) {
+        let left = user.remove(&user_id);
+        shared.latency.remove(&user_id);
+        shared.connected.remove(&user_id);
+        shared.bots.retain(|(id, _)| *id != user_id);
+        if shared.host == Some(user_id) {
+            shared.host = user.keys().find(|id| !is_bot(&shared.bots, id)).copied();
+        }
+        let GameState::Init {
+            phase,
+            deck,
+            turn_order,
+            rematch,
+            ..
+        } = &mut shared.game
+        else {
+            return;
+        };
+        if matches!(phase, Phase::Victory | Phase::Defeat) {
+            // The leaver may have been the last one the others waited for.
+            rematch.retain(|(id, _)| *id != user_id);
+            turn_order.retain(|id| *id != user_id);
+            start_rematch(shared, user);
+            return;
+        }
+        if let Some(UserState::Init { hand }) = left {
+            deck.discard(&mut hand.into_iter().collect());
+        }
+        let Some(pos) = turn_order.iter().position(|id| *id == user_id) else {
+            return;
+        };
+        turn_order.remove(pos);
+        if turn_order.is_empty() {
+            *phase = Phase::Defeat;
+            return;
+        }
+        // Whoever would have followed the leaver picks up their turn.
+        let next = turn_order[pos % turn_order.len()];
+        match *phase {
+            Phase::Play(id) | Phase::Jester(id) | Phase::Defend(id) if id == user_id => {
+                *phase = Phase::Play(next);
+            }
+            _ => (),
+        }
+    }
+    fn end(shared: &mut SharedState) -> bool {
+        match &mut shared.game {
+            GameState::Init { phase, .. } if !matches!(phase, Phase::Victory | Phase::Defeat) => {
+                *phase = Phase::Defeat;
+                true
+            }
+            _ => false,
+        }
+    }
+    fn hint(
+        shared: &SharedState,
+        user: &HashMap<UserId, UserState>,
+        user_id: UserId,
+    ) -> Option<ServerMsg> {
+        let view = View::new(shared, user, user_id)?;
+        Some(ServerMsg::Hint {
+            hints: hints(&view),
+        })
+    }
+    fn status(shared: &SharedState) -> &'static str {
+        match &shared.game {
+            GameState::Uninit => "lobby",
+            GameState::Init { phase, .. } => match phase {
+                Phase::Play(_) => "play",
+                Phase::Jester(_) => "jester",
+                Phase::Defend(_) => "defend",
+                Phase::Victory => "victory",
+                Phase::Defeat => "defeat",
+            },
+        }
+    }
+    fn dealt(shared: &SharedState) -> Option<(u32, u64)> {
+        match &shared.game {
+            GameState::Init { number, seed, .. } => Some((*number, *seed)),
+            GameState::Uninit => None,
+        }
+    }
+    fn summary(shared: &SharedState, _user: &HashMap<UserId, UserState>) -> Option<Outcome> {
+        match &shared.game {
+            GameState::Init {
+                phase, deck, stats, ..
+            } if matches!(phase, Phase::Victory | Phase::Defeat) => Some(Outcome {
+                victory: *phase == Phase::Victory,
+                enemies_defeated: deck.defeated_count() as u8,
+                defeated_by: deck
+                    .battling()
+                    .filter(|_| *phase == Phase::Defeat)
+                    .map(JsCard::from),
+                stats: stats.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// The seed for game `number` at a table: the rules' seed stepped by the
+/// number if they fix one, so each rematch deals differently, otherwise a
+/// fresh one.
+fn seed(rules: &Ruleset, number: u32) -> u64 {
+    rules.seed.map_or_else(
+        || rand::rng().random(),
+        |seed| seed.wrapping_add(number as u64),
+    )
+}
+
+/// Humans with a connection open. Only they vote on a rematch.
+fn voters<'a>(
+    shared: &'a SharedState,
+    user: &'a HashMap<UserId, UserState>,
+) -> impl Iterator<Item = &'a UserId> {
+    user.keys()
+        .filter(|id| shared.connected.contains(*id) && !is_bot(&shared.bots, id))
+}
+
+/// Starts a game with `turn_order` seated in that order, first player first.
+fn deal(
+    game: &mut GameState,
+    rules: &Ruleset,
+    user: &mut HashMap<UserId, UserState>,
+    turn_order: ArrayVec<UserId, MAX_PLAYERS>,
+    seed: u64,
+    number: u32,
+) {
+    let hand_size = HAND_SIZE - user.len();
+    let jesters = rules.jesters(user.len());
+    game.init(jesters, turn_order, seed, number);
+    if let GameState::Init {
+        deck, turn_order, ..
+    } = game
+    {
+        turn_order.iter().for_each(|id| {
+            if let Some(u) = user.get_mut(id) {
+                let mut hand = ArrayVec::new();
+                hand.extend((0..hand_size).map_while(|_| deck.draw()));
+                *u = UserState::Init { hand };
+            }
+        });
+    }
+}
+
+/// Deals the next game once every connected human has voted for a rematch.
+/// Bots, and players away from the table, keep their seats without a vote.
+fn start_rematch(shared: &mut SharedState, user: &mut HashMap<UserId, UserState>) {
+    let GameState::Init {
+        phase: Phase::Victory | Phase::Defeat,
+        turn_order,
+        rematch,
+        number,
+        ..
+    } = &shared.game
+    else {
+        return;
+    };
+    let voted = |id: &UserId| rematch.iter().any(|(voter, _)| voter == id);
+    if voters(shared, user).next().is_none() || !voters(shared, user).all(voted) {
+        return;
+    }
+    let number = number + 1;
+    let seed = seed(&shared.rules, number);
+    let mut turn_order = turn_order.clone();
+    if rematch.iter().all(|(_, rotate)| *rotate) {
+        turn_order.rotate_left(1);
+    } else {
+        turn_order.sort();
+        turn_order.shuffle(&mut StdRng::seed_from_u64(seed));
+    }
+    deal(
+        &mut shared.game,
+        &shared.rules,
+        user,
+        turn_order,
+        seed,
+        number,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Card, PROTOCOL_VERSION};
+
+    /// A seeded table of `players`, dealt and ready for the first play.
+    fn start(players: u8) -> (SharedState, HashMap<UserId, UserState>, Vec<UserId>) {
+        let rules = Ruleset {
+            min_players: players,
+            max_players: players,
+            seed: Some(7),
+            ..Ruleset::default()
+        };
+        let mut shared = RegicideAction::shared(&rules);
+        let mut user: HashMap<UserId, UserState> = (1..=players)
+            .map(|seat| {
+                (
+                    UserId::from(&format!("p{seat}")).unwrap(),
+                    UserState::default(),
+                )
+            })
+            .collect();
+        let first = *user.keys().next().unwrap();
+        RegicideAction::Init.update(&mut shared, &mut user, first);
+        let GameState::Init { turn_order, .. } = &shared.game else {
+            panic!("game did not start");
+        };
+        let order = turn_order.to_vec();
+        (shared, user, order)
+    }
+
+    fn hand(user: &mut HashMap<UserId, UserState>, user_id: UserId, cards: &[Card]) {
+        let hand = cards.iter().copied().collect();
+        user.insert(user_id, UserState::Init { hand });
+    }
+
+    fn phase(shared: &SharedState) -> Phase {
+        match &shared.game {
+            GameState::Init { phase, .. } => *phase,
+            GameState::Uninit => panic!("game not started"),
+        }
+    }
+
+    fn damage(shared: &mut SharedState) -> &mut u8 {
+        match &mut shared.game {
+            GameState::Init { damage, .. } => damage,
+            GameState::Uninit => panic!("game not started"),
+        }
+    }
+
+    fn deck(shared: &SharedState) -> &crate::Deck {
+        match &shared.game {
+            GameState::Init { deck, .. } => deck,
+            GameState::Uninit => panic!("game not started"),
+        }
+    }
+
+    fn play(cards: &[u8]) -> RegicideAction {
+        RegicideAction::Play {
+            cards: cards.iter().copied().collect(),
+        }
+    }
+
+    fn discard(cards: &[u8]) -> RegicideAction {
+        RegicideAction::Discard {
+            cards: cards.iter().copied().collect(),
+        }
+    }
+
+    /// Ends the game in defeat with everyone at the table connected.
+    fn lose(shared: &mut SharedState, user: &mut HashMap<UserId, UserState>, order: &[UserId]) {
+        order
+            .iter()
+            .for_each(|id| RegicideAction::connected(shared, user, *id, true));
+        if let GameState::Init { phase, .. } = &mut shared.game {
+            *phase = Phase::Defeat;
+        }
+    }
+
+    fn rematch(shared: &mut SharedState, user: &mut HashMap<UserId, UserState>, user_id: UserId) {
+        RegicideAction::Rematch { rotate: false }.update(shared, user, user_id);
+    }
+
+    #[test]
+    fn init_deals_hands_and_draws_the_first_enemy() {
+        let (shared, user, order) = start(2);
+        assert_eq!(phase(&shared), Phase::Play(order[0]));
+        // The court is dealt jacks first.
+        assert_eq!(deck(&shared).battling().map(Card::value), Some(10));
+        assert_eq!(deck(&shared).defeated_count(), 0);
+        user.values().for_each(|u| {
+            assert!(matches!(u, UserState::Init { hand } if hand.len() == HAND_SIZE - 2));
+        });
+    }
+
+    #[test]
+    fn a_game_deals_again_from_its_seed() {
+        let rules = Ruleset {
+            min_players: 1,
+            ..Ruleset::default()
+        };
+        let mut shared = RegicideAction::shared(&rules);
+        let ann = UserId::from("ann").unwrap();
+        let mut user = HashMap::from([(ann, UserState::default())]);
+        assert_eq!(RegicideAction::dealt(&shared), None);
+        RegicideAction::Init.update(&mut shared, &mut user, ann);
+        let (_, seed) = RegicideAction::dealt(&shared).unwrap();
+        let mut again = GameState::Uninit;
+        let mut hands = HashMap::from([(ann, UserState::default())]);
+        deal(
+            &mut again,
+            &rules,
+            &mut hands,
+            [ann].into_iter().collect(),
+            seed,
+            0,
+        );
+        let GameState::Init {
+            deck: mut replayed, ..
+        } = again
+        else {
+            unreachable!()
+        };
+        let mut dealt = deck(&shared).clone();
+        assert_eq!(replayed.battling(), dealt.battling());
+        assert!((0..32).all(|_| replayed.draw() == dealt.draw()));
+        let (UserState::Init { hand: a }, UserState::Init { hand: b }) =
+            (&hands[&ann], &user[&ann])
+        else {
+            panic!("hands not dealt");
+        };
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn a_rematch_waits_for_every_connected_player() {
+        let (mut shared, mut user, order) = start(2);
+        lose(&mut shared, &mut user, &order);
+        rematch(&mut shared, &mut user, order[0]);
+        assert_eq!(phase(&shared), Phase::Defeat);
+        rematch(&mut shared, &mut user, order[1]);
+        assert!(matches!(phase(&shared), Phase::Play(_)));
+    }
+
+    #[test]
+    fn a_disconnected_player_does_not_hold_up_the_rematch() {
+        let (mut shared, mut user, order) = start(2);
+        lose(&mut shared, &mut user, &order);
+        rematch(&mut shared, &mut user, order[0]);
+        RegicideAction::connected(&mut shared, &mut user, order[1], false);
+        assert!(matches!(phase(&shared), Phase::Play(_)));
+        // They keep their seat and are dealt in.
+        assert!(matches!(&user[&order[1]], UserState::Init { hand } if !hand.is_empty()));
+    }
+
+    #[test]
+    fn nobody_connected_starts_no_rematch() {
+        let (mut shared, mut user, order) = start(2);
+        lose(&mut shared, &mut user, &order);
+        rematch(&mut shared, &mut user, order[0]);
+        RegicideAction::connected(&mut shared, &mut user, order[0], false);
+        RegicideAction::connected(&mut shared, &mut user, order[1], false);
+        assert_eq!(phase(&shared), Phase::Defeat);
+    }
+
+    #[test]
+    fn each_rematch_is_numbered_and_dealt_from_its_own_seed() {
+        let (mut shared, mut user, order) = start(1);
+        assert_eq!(RegicideAction::dealt(&shared), Some((0, 7)));
+        lose(&mut shared, &mut user, &order);
+        rematch(&mut shared, &mut user, order[0]);
+        assert_eq!(RegicideAction::dealt(&shared), Some((1, 8)));
+        lose(&mut shared, &mut user, &order);
+        rematch(&mut shared, &mut user, order[0]);
+        assert_eq!(RegicideAction::dealt(&shared), Some((2, 9)));
+    }
+
+    #[test]
+    fn older_peers_hear_a_finished_game_as_victory_or_defeat() {
+        let (mut shared, mut user, order) = start(1);
+        lose(&mut shared, &mut user, &order);
+        let (_, msg) = RegicideAction::msg(&shared, &user).remove(0);
+        assert!(matches!(
+            RegicideAction::downgrade(msg.clone(), PROTOCOL_VERSION),
+            ServerMsg::Finished { ref outcome, seats: 1, .. } if !outcome.victory
+        ));
+        assert!(matches!(
+            RegicideAction::downgrade(msg, 4),
+            ServerMsg::Defeat { seats: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn a_blow_that_does_not_kill_lets_the_enemy_strike() {
+        let (mut shared, mut user, order) = start(1);
+        hand(&mut user, order[0], &[Card::HeartFive, Card::HeartTen]);
+        play(&[0]).update(&mut shared, &mut user, order[0]);
+        assert_eq!(phase(&shared), Phase::Defend(order[0]));
+        assert_eq!(*damage(&mut shared), 5);
+    }
+
+    #[test]
+    fn surviving_the_attack_passes_the_turn() {
+        let (mut shared, mut user, order) = start(2);
+        hand(&mut user, order[0], &[Card::HeartFive, Card::HeartTen]);
+        play(&[0]).update(&mut shared, &mut user, order[0]);
+        discard(&[0]).update(&mut shared, &mut user, order[0]);
+        assert_eq!(phase(&shared), Phase::Play(order[1]));
+    }
+
+    #[test]
+    fn falling_short_on_defense_loses() {
+        let (mut shared, mut user, order) = start(1);
+        hand(&mut user, order[0], &[Card::HeartFive, Card::HeartTwo]);
+        play(&[0]).update(&mut shared, &mut user, order[0]);
+        discard(&[0]).update(&mut shared, &mut user, order[0]);
+        assert_eq!(phase(&shared), Phase::Defeat);
+    }
+
+    #[test]
+    fn an_exact_kill_resets_damage_and_the_killer_plays_on() {
+        let (mut shared, mut user, order) = start(1);
+        let enemy = *deck(&shared).battling().unwrap();
+        *damage(&mut shared) = 15;
+        hand(&mut user, order[0], &[Card::HeartFive, Card::HeartTen]);
+        play(&[0]).update(&mut shared, &mut user, order[0]);
+        assert_eq!(phase(&shared), Phase::Play(order[0]));
+        assert_eq!(*damage(&mut shared), 0);
+        assert_eq!(deck(&shared).defeated_count(), 1);
+        assert_ne!(deck(&shared).battling(), Some(&enemy));
+        // Exact kills go on top of the library.
+        let GameState::Init { deck, .. } = &mut shared.game else {
+            unreachable!()
+        };
+        assert_eq!(deck.draw(), Some(enemy));
+    }
+
+    #[test]
+    fn an_overkill_sends_the_enemy_to_the_bottom() {
+        let (mut shared, mut user, order) = start(1);
+        let enemy = *deck(&shared).battling().unwrap();
+        *damage(&mut shared) = 15;
+        hand(&mut user, order[0], &[Card::HeartTen]);
+        play(&[0]).update(&mut shared, &mut user, order[0]);
+        assert_eq!(phase(&shared), Phase::Play(order[0]));
+        assert_eq!(*damage(&mut shared), 0);
+        let GameState::Init { deck, .. } = &mut shared.game else {
+            unreachable!()
+        };
+        assert_ne!(deck.draw(), Some(enemy));
+    }
+
+    #[test]
+    fn an_empty_play_yields_to_the_enemy() {
+        let (mut shared, mut user, order) = start(2);
+        play(&[]).update(&mut shared, &mut user, order[0]);
+        assert_eq!(phase(&shared), Phase::Defend(order[0]));
+        assert_eq!(*damage(&mut shared), 0);
+    }
+
+    #[test]
+    fn the_last_player_to_act_cannot_yield_again() {
+        let (mut shared, mut user, order) = start(2);
+        hand(&mut user, order[0], &[Card::HeartTen, Card::HeartFive]);
+        hand(&mut user, order[1], &[Card::HeartTwo]);
+        play(&[]).update(&mut shared, &mut user, order[0]);
+        discard(&[0]).update(&mut shared, &mut user, order[0]);
+        assert_eq!(phase(&shared), Phase::Play(order[1]));
+        play(&[]).update(&mut shared, &mut user, order[1]);
+        assert_eq!(phase(&shared), Phase::Play(order[1]));
+        // Playing a card clears the run of yields.
+        play(&[0]).update(&mut shared, &mut user, order[1]);
+        assert_eq!(phase(&shared), Phase::Defend(order[1]));
+    }
+
+    #[test]
+    fn a_lone_player_never_yields() {
+        let (mut shared, mut user, order) = start(1);
+        hand(&mut user, order[0], &[Card::HeartTwo]);
+        play(&[]).update(&mut shared, &mut user, order[0]);
+        assert_eq!(phase(&shared), Phase::Play(order[0]));
+        hand(&mut user, order[0], &[]);
+        play(&[]).update(&mut shared, &mut user, order[0]);
+        assert_eq!(phase(&shared), Phase::Defeat);
+    }
+
+    #[test]
+    fn playing_a_jester_hands_the_choice_to_its_player() {
+        let (mut shared, mut user, order) = start(2);
+        hand(&mut user, order[0], &[Card::Joker]);
+        play(&[0]).update(&mut shared, &mut user, order[0]);
+        assert_eq!(phase(&shared), Phase::Jester(order[0]));
+        RegicideAction::Jester { player: order[1] }.update(&mut shared, &mut user, order[0]);
+        assert_eq!(phase(&shared), Phase::Play(order[1]));
+    }
 }
@@ -0,0 +1,377 @@
+//! Heuristic players. A bot only looks at what a human in its seat could see:
+//! its own hand, the enemy, the resolving pile and public card counts.
+
+use crate::{Card, Combo, GameState, Phase, RegicideAction, SharedState, Suit, UserState};
+use actor::UserId;
+use arrayvec::ArrayVec;
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::IndexedRandom};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use ts_rs::TS;
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../frontend/src/bindings/")]
+pub enum Difficulty {
+    /// Plays any legal move.
+    Easy,
+    /// Kills when it can and otherwise hits as hard as possible.
+    #[default]
+    Normal,
+    /// Also weighs suit powers and saves the jester for spade royals.
+    Hard,
+}
+
+/// The public game state plus one player's hand.
+#[derive(Clone, Debug)]
+pub struct View<'a> {
+    pub user_id: UserId,
+    pub phase: Phase,
+    pub hand: &'a [Card],
+    pub enemy: Card,
+    pub damage: u8,
+    /// A jester is in play, cancelling a spade royal's immunity.
+    pub jester: bool,
+    /// Attack already blocked by spades in play.
+    pub shield: u8,
+    pub library: usize,
+    pub discard: usize,
+    /// Turn order with the number of cards in each hand.
+    pub players: Vec<(UserId, usize)>,
+}
+
+impl<'a> View<'a> {
+    pub fn new(
+        shared: &SharedState,
+        user: &'a HashMap<UserId, UserState>,
+        user_id: UserId,
+    ) -> Option<Self> {
+        let GameState::Init {
+            phase,
+            deck,
+            turn_order,
+            damage,
+            ..
+        } = &shared.game
+        else {
+            return None;
+        };
+        let UserState::Init { hand } = user.get(&user_id)? else {
+            return None;
+        };
+        let players = turn_order
+            .iter()
+            .filter_map(|id| match user.get(id) {
+                Some(UserState::Init { hand }) => Some((*id, hand.len())),
+                _ => None,
+            })
+            .collect();
+        Some(Self {
+            user_id,
+            phase: *phase,
+            hand,
+            enemy: *deck.battling()?,
+            damage: *damage,
+            jester: deck.jester(),
+            shield: deck.defense_value(),
+            library: deck.library_count(),
+            discard: deck.discard_count(),
+            players,
+        })
+    }
+    /// Damage still needed to defeat the enemy.
+    pub fn health(&self) -> u8 {
+        (self.enemy.value() * 2).saturating_sub(self.damage)
+    }
+    /// Whether the enemy ignores spades, as a spade royal does until a
+    /// jester is played. No other suit's power is ever blocked.
+    pub fn immune_to_spades(&self) -> bool {
+        !self.jester && self.enemy.suit() == Some(Suit::Spade)
+    }
+    /// Damage the enemy deals when it attacks.
+    pub fn attack(&self) -> u8 {
+        let shield = if self.immune_to_spades() {
+            0
+        } else {
+            self.shield
+        };
+        self.enemy.value().saturating_sub(shield)
+    }
+}
+
+/// Something that picks moves for a seat. Returns `None` when it is not the
+/// seat's turn to act.
+pub trait Policy {
+    fn act(&mut self, view: &View) -> Option<RegicideAction>;
+}
+
+pub struct Bot {
+    difficulty: Difficulty,
+    rng: StdRng,
+}
+
+impl Bot {
+    pub fn new(difficulty: Difficulty) -> Self {
+        Self::with_rng(difficulty, StdRng::from_rng(&mut rand::rng()))
+    }
+    pub fn with_rng(difficulty: Difficulty, rng: StdRng) -> Self {
+        Self { difficulty, rng }
+    }
+    fn play(&mut self, view: &View) -> RegicideAction {
+        let plays = plays(view.hand);
+        let choice = match self.difficulty {
+            Difficulty::Easy => plays.choose(&mut self.rng),
+            Difficulty::Normal => best_kill(view, &plays).or_else(|| {
+                plays
+                    .iter()
+                    .filter(|(_, combo)| !matches!(combo, Combo::Jester))
+                    .max_by_key(|(_, combo)| combo.strength())
+                    // A lone jester still beats a yield, which may not be allowed.
+                    .or_else(|| plays.first())
+            }),
+            Difficulty::Hard => best_kill(view, &plays)
+                .or_else(|| plays.iter().max_by_key(|(_, combo)| score(view, combo))),
+        };
+        // No legal play means an empty hand, so yield.
+        let cards = choice.map(|(cards, _)| cards.clone()).unwrap_or_default();
+        RegicideAction::Play { cards }
+    }
+    fn discard(&mut self, view: &View) -> RegicideAction {
+        let need = view.attack();
+        let cards = match self.difficulty {
+            Difficulty::Easy => {
+                let mut order: Vec<u8> = (0..view.hand.len() as u8).collect();
+                order.sort_by_cached_key(|_| self.rng.random::<u32>());
+                let mut total = 0;
+                order
+                    .into_iter()
+                    .take_while(|i| {
+                        let more = total < need;
+                        total += view.hand[*i as usize].strength();
+                        more
+                    })
+                    .collect()
+            }
+            Difficulty::Normal | Difficulty::Hard => cheapest_defense(view.hand, need),
+        };
+        RegicideAction::Discard { cards }
+    }
+    fn jester(&mut self, view: &View) -> RegicideAction {
+        let player = match self.difficulty {
+            Difficulty::Easy => view.players.choose(&mut self.rng).map(|(id, _)| *id),
+            // Ties go to whoever comes first after us in turn order.
+            Difficulty::Normal | Difficulty::Hard => {
+                let seat = view
+                    .players
+                    .iter()
+                    .position(|(id, _)| *id == view.user_id)
+                    .unwrap_or_default();
+                let len = view.players.len();
+                (1..=len)
+                    .map(|offset| view.players[(seat + offset) % len])
+                    .rev()
+                    .max_by_key(|(_, cards)| *cards)
+                    .map(|(id, _)| id)
+            }
+        };
+        RegicideAction::Jester {
+            player: player.unwrap_or(view.user_id),
+        }
+    }
+}
+
+impl Policy for Bot {
+    fn act(&mut self, view: &View) -> Option<RegicideAction> {
+        match view.phase {
+            Phase::Play(id) if id == view.user_id => Some(self.play(view)),
+            Phase::Defend(id) if id == view.user_id => Some(self.discard(view)),
+            Phase::Jester(id) if id == view.user_id => Some(self.jester(view)),
+            _ => None,
+        }
+    }
+}
+
+/// Every legal combo in `hand`, with the hand indices that make it up.
+pub fn plays(hand: &[Card]) -> Vec<(ArrayVec<u8, 4>, Combo)> {
+    (1u16..1 << hand.len())
+        .filter(|mask| mask.count_ones() <= 4)
+        .filter_map(|mask| {
+            let cards: ArrayVec<u8, 4> = (0..hand.len() as u8)
+                .filter(|i| mask & (1 << i) != 0)
+                .collect();
+            let combo = Combo::from_cards(cards.iter().map(|i| hand[*i as usize]).collect())?;
+            Some((cards, combo))
+        })
+        .collect()
+}
+
+/// Discards that survive `need` damage with no card to spare, cheapest first.
+/// Empty when the hand cannot survive.
+pub fn defenses(hand: &[Card], need: u8) -> Vec<ArrayVec<u8, 8>> {
+    let indices = |mask: u16| (0..hand.len() as u8).filter(move |i| mask & (1 << i) != 0);
+    let strength = |mask: u16| -> u16 {
+        indices(mask)
+            .map(|i| hand[i as usize].strength() as u16)
+            .sum()
+    };
+    let need = need as u16;
+    let mut masks: Vec<u16> = (0u16..1 << hand.len())
+        .filter(|&mask| {
+            strength(mask) >= need && indices(mask).all(|i| strength(mask & !(1 << i)) < need)
+        })
+        .collect();
+    masks.sort_by_key(|&mask| (strength(mask), mask.count_ones()));
+    masks
+        .into_iter()
+        .map(|mask| indices(mask).collect())
+        .collect()
+}
+
+/// The cheapest set of cards whose strength covers `need`. Spends the whole
+/// hand when nothing does, since the party is lost either way.
+pub fn cheapest_defense(hand: &[Card], need: u8) -> ArrayVec<u8, 8> {
+    defenses(hand, need)
+        .into_iter()
+        .next()
+        .unwrap_or_else(|| (0..hand.len() as u8).collect())
+}
+
+/// The kill that overshoots least, preferring exact kills and fewer cards.
+fn best_kill<'p>(
+    view: &View,
+    plays: &'p [(ArrayVec<u8, 4>, Combo)],
+) -> Option<&'p (ArrayVec<u8, 4>, Combo)> {
+    let health = view.health();
+    plays
+        .iter()
+        .filter(|(_, combo)| !matches!(combo, Combo::Jester) && combo.strength() >= health)
+        .min_by_key(|(cards, combo)| (combo.strength() - health, cards.len()))
+}
+
+/// Damage plus a rough value for the suit powers a combo triggers.
+fn score(view: &View, combo: &Combo) -> u16 {
+    if matches!(combo, Combo::Jester) {
+        // Only worth it against a spade royal while we hold spades to block with.
+        let blocked = view
+            .hand
+            .iter()
+            .filter(|card| view.immune_to_spades() && card.suit() == Some(Suit::Spade))
+            .count();
+        return if blocked >= 2 { u16::MAX } else { 0 };
+    }
+    let power = |suit: Suit| {
+        if suit == Suit::Spade && view.immune_to_spades() {
+            0
+        } else {
+            combo.suit_value(suit) as u16
+        }
+    };
+    let heal = power(Suit::Heart).min(view.discard as u16);
+    let draw = if view.hand.len() <= 3 {
+        power(Suit::Diamond)
+    } else {
+        0
+    };
+    let shield = power(Suit::Spade).min(view.attack() as u16);
+    combo.strength() as u16 * 2 + heal + draw + shield
+}
+
+/// Moves for every bot whose turn it is.
+pub fn bot_actions(
+    shared: &SharedState,
+    user: &HashMap<UserId, UserState>,
+) -> Vec<(UserId, RegicideAction)> {
+    shared
+        .bots
+        .iter()
+        .filter_map(|(bot_id, difficulty)| {
+            let view = View::new(shared, user, *bot_id)?;
+            Some((*bot_id, Bot::new(*difficulty).act(&view)?))
+        })
+        .collect()
+}
+
+/// A fresh id for a bot seat.
+pub fn bot_id(rng: &mut impl Rng) -> UserId {
+    UserId::from(&format!("bot-{:08x}", rng.random::<u32>())).expect("bot id fits")
+}
+
+pub fn is_bot(bots: &[(UserId, Difficulty)], user_id: &UserId) -> bool {
+    bots.iter().any(|(id, _)| id == user_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view(hand: &[Card]) -> View<'_> {
+        let user_id = UserId::from("bot").unwrap();
+        View {
+            user_id,
+            phase: Phase::Play(user_id),
+            hand,
+            enemy: Card::SpadeJack,
+            damage: 0,
+            jester: false,
+            shield: 0,
+            library: 20,
+            discard: 0,
+            players: vec![(user_id, hand.len())],
+        }
+    }
+
+    fn played(difficulty: Difficulty, hand: &[Card]) -> Vec<u8> {
+        let mut bot = Bot::with_rng(difficulty, StdRng::seed_from_u64(0));
+        match bot.act(&view(hand)) {
+            Some(RegicideAction::Play { cards }) => cards.to_vec(),
+            other => panic!("expected a play, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn every_difficulty_plays_a_lone_jester() {
+        for difficulty in [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard] {
+            assert_eq!(played(difficulty, &[Card::Joker]), [0], "{difficulty:?}");
+        }
+    }
+
+    #[test]
+    fn normal_keeps_the_jester_while_it_has_another_play() {
+        assert_eq!(
+            played(Difficulty::Normal, &[Card::Joker, Card::HeartTwo]),
+            [1]
+        );
+    }
+
+    #[test]
+    fn normal_prefers_the_closest_kill() {
+        let hand = [Card::HeartTen, Card::ClubTen, Card::DiamondSeven];
+        let mut bot = Bot::with_rng(Difficulty::Normal, StdRng::seed_from_u64(0));
+        let mut view = view(&hand);
+        view.damage = 10;
+        match bot.act(&view) {
+            Some(RegicideAction::Play { cards }) => assert_eq!(cards.as_slice(), [0]),
+            other => panic!("expected a play, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn only_a_spade_royal_blocks_a_power() {
+        let hand = [Card::ClubFive, Card::SpadeFive];
+        let mut view = view(&hand);
+        view.enemy = Card::ClubJack;
+        // Clubs still double against a club royal.
+        assert_eq!(score(&view, &Combo::Single(Card::ClubFive)), 20);
+        view.enemy = Card::SpadeJack;
+        assert_eq!(score(&view, &Combo::Single(Card::SpadeFive)), 10);
+        view.jester = true;
+        assert_eq!(score(&view, &Combo::Single(Card::SpadeFive)), 15);
+    }
+
+    #[test]
+    fn cheapest_defense_covers_the_attack() {
+        let hand = [Card::HeartTen, Card::SpadeThree, Card::ClubFour];
+        assert_eq!(cheapest_defense(&hand, 7).as_slice(), [2]);
+        // Nothing survives, so the whole hand goes.
+        assert_eq!(cheapest_defense(&hand, 30).as_slice(), [0, 1, 2]);
+    }
+}
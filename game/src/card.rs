@@ -1,4 +1,4 @@
-use macros::cards;
+use macros::{cards, list_cards};
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
 use utoipa::ToSchema;
@@ -42,6 +42,19 @@ pub struct JsCard {
     pub value: u8,
 }
 
+impl JsCard {
+    /// The card this was made from, if `rank` names one.
+    pub fn card(&self) -> Option<Card> {
+        list_cards!(
+            suits: [Heart, Spade, Diamond, Club],
+            ranks: [Ace, Two, Three, Four, Five, Six, Seven, Eight, Nine, Ten, Jack, Queen, King],
+            other: [Joker = 1]
+        )
+        .into_iter()
+        .find(|card| card.to_string() == self.rank)
+    }
+}
+
 impl From<Card> for JsCard {
     fn from(card: Card) -> Self {
         Self {
@@ -62,7 +75,7 @@ impl From<&Card> for JsCard {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub enum Combo {
     Jester,
     Single(Card),
@@ -1,7 +1,8 @@
 use crate::{Card, Combo, JsCard, Suit};
 use arrayvec::ArrayVec;
 use macros::list_cards;
-use rand::{rngs::ThreadRng, seq::SliceRandom};
+use rand::{SeedableRng, rngs::StdRng, seq::SliceRandom};
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
 macro_rules! list_deck {
@@ -14,9 +15,10 @@ macro_rules! list_deck {
     };
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Deck {
-    rng: ThreadRng,
+    #[serde(skip, default = "fresh_rng")]
+    rng: StdRng,
     library: VecDeque<Card>,
     discard: Vec<Card>,
     court: ArrayVec<Card, 12>,
@@ -24,12 +26,17 @@ pub struct Deck {
     resolving: ArrayVec<Combo, 17>,
 }
 
+/// Restored decks reshuffle from fresh entropy.
+fn fresh_rng() -> StdRng {
+    StdRng::from_rng(&mut rand::rng())
+}
+
 impl Deck {
-    pub fn new(jesters: usize, mut rng: ThreadRng) -> Self {
+    pub fn new(jesters: usize, mut rng: StdRng) -> Self {
         let library = Self::library(&mut rng, jesters);
         let discard = Vec::new();
-        let court = Self::court(&mut rng);
-        let battling = None;
+        let mut court = Self::court(&mut rng);
+        let battling = court.pop();
         let resolving = ArrayVec::new();
         Self {
             rng,
@@ -40,6 +47,11 @@ impl Deck {
             resolving,
         }
     }
+    /// The shuffle itself, which snapshots leave out. Only tools that
+    /// search a deal with full knowledge of the deck need it.
+    pub fn rng(&self) -> &StdRng {
+        &self.rng
+    }
     pub fn battling(&self) -> Option<&Card> {
         self.battling.as_ref()
     }
@@ -49,27 +61,28 @@ impl Deck {
     pub fn discard_count(&self) -> usize {
         self.discard.len()
     }
+    pub fn defeated_count(&self) -> usize {
+        12 - self.court.len() - self.battling.iter().count()
+    }
     pub fn resolving(&self) -> Vec<Vec<JsCard>> {
         self.resolving.iter().map(|c| c.js_cards()).collect()
     }
     pub fn jester(&self) -> bool {
-        self.resolving
-            .iter()
-            .any(|c| matches!(c, Combo::Single(Card::Joker)))
+        self.resolving.iter().any(|c| matches!(c, Combo::Jester))
     }
     pub fn defense_value(&self) -> u8 {
         self.resolving
             .iter()
             .fold(0, |def, c| def + c.suit_value(Suit::Spade))
     }
-    pub fn draw(&mut self) -> Card {
-        self.library.pop_front().unwrap_or_else(|| {
+    /// Draws from the library, reshuffling the discard pile into it when empty.
+    /// Returns `None` once both are exhausted.
+    pub fn draw(&mut self) -> Option<Card> {
+        if self.library.is_empty() {
             self.discard.shuffle(&mut self.rng);
             self.library = std::mem::take(&mut self.discard).into();
-            self.library
-                .pop_front()
-                .expect("Should never happen: tried to draw from empty library and discard")
-        })
+        }
+        self.library.pop_front()
     }
     pub fn discard(&mut self, cards: &mut Vec<Card>) {
         self.discard.append(cards);
@@ -93,12 +106,16 @@ impl Deck {
     pub fn play_cards(&mut self, combo: Combo) {
         self.resolving.push(combo);
     }
-    pub fn heal(&mut self, heal: usize) {
-        let i = self.discard.len() - std::cmp::min(heal, self.discard.len());
+    /// Moves up to `heal` random cards from the discard pile to the bottom of
+    /// the library, returning how many moved.
+    pub fn heal(&mut self, heal: usize) -> usize {
+        let healed = std::cmp::min(heal, self.discard.len());
+        let i = self.discard.len() - healed;
         self.discard.shuffle(&mut self.rng);
         self.library.append(&mut self.discard.split_off(i).into());
+        healed
     }
-    pub fn court(rng: &mut ThreadRng) -> ArrayVec<Card, 12> {
+    pub fn court(rng: &mut StdRng) -> ArrayVec<Card, 12> {
         let mut court = ArrayVec::new();
         let mut kings = list_cards!(suits: [Heart, Spade, Diamond, Club], ranks: [King], other: []);
         kings.shuffle(rng);
@@ -112,7 +129,7 @@ impl Deck {
         court.try_extend_from_slice(&jacks).expect("jacks");
         court
     }
-    pub fn library(rng: &mut ThreadRng, jesters: usize) -> Vec<Card> {
+    pub fn library(rng: &mut StdRng, jesters: usize) -> Vec<Card> {
         let mut library = match jesters {
             2 => list_deck!(2),
             1 => list_deck!(1),
@@ -122,3 +139,45 @@ impl Deck {
         library
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deck() -> Deck {
+        Deck::new(0, StdRng::seed_from_u64(1))
+    }
+
+    #[test]
+    fn a_new_deck_is_already_battling() {
+        let deck = deck();
+        assert!(deck.battling().is_some());
+        assert_eq!(deck.library_count(), 40);
+        assert_eq!(deck.defeated_count(), 0);
+    }
+
+    #[test]
+    fn draw_reshuffles_the_discard_pile_then_runs_out() {
+        let mut deck = deck();
+        let mut drawn: Vec<Card> = std::iter::from_fn(|| deck.draw()).collect();
+        assert_eq!(drawn.len(), 40);
+        assert_eq!(deck.draw(), None);
+        let back = drawn.split_off(38);
+        deck.discard(&mut back.clone());
+        let mut again: Vec<Card> = std::iter::from_fn(|| deck.draw()).collect();
+        again.sort_by_key(|card| *card as u8);
+        let mut back = back;
+        back.sort_by_key(|card| *card as u8);
+        assert_eq!(again, back);
+    }
+
+    #[test]
+    fn heal_moves_at_most_the_discard_pile() {
+        let mut deck = deck();
+        let mut cards: Vec<Card> = (0..3).filter_map(|_| deck.draw()).collect();
+        deck.discard(&mut cards);
+        assert_eq!(deck.heal(5), 3);
+        assert_eq!(deck.discard_count(), 0);
+        assert_eq!(deck.library_count(), 40);
+    }
+}
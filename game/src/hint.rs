@@ -0,0 +1,248 @@
+use crate::{Card, Combo, JsCard, Phase, RegicideAction, Suit, View, defenses, plays};
+use arrayvec::ArrayVec;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use utoipa::ToSchema;
+
+/// Suggestions sent per request.
+const MAX_HINTS: usize = 5;
+
+/// A suggested move with the reasoning behind it.
+#[derive(Clone, Debug, Serialize, Deserialize, TS, ToSchema)]
+#[ts(export, export_to = "../../frontend/src/bindings/")]
+pub struct Hint {
+    #[schema(value_type = Object)]
+    pub action: RegicideAction,
+    pub cards: Vec<JsCard>,
+    pub reasons: Vec<String>,
+}
+
+/// Ranked suggestions for the player in `view`, best first. Built from the
+/// view alone, so they never reveal more than the player can already see.
+pub fn hints(view: &View) -> Vec<Hint> {
+    let mut ranked = match view.phase {
+        Phase::Play(id) if id == view.user_id => play_hints(view),
+        Phase::Defend(id) if id == view.user_id => defend_hints(view),
+        Phase::Jester(id) if id == view.user_id => jester_hints(view),
+        _ => Vec::new(),
+    };
+    ranked.sort_by_key(|(score, _)| -score);
+    ranked
+        .into_iter()
+        .take(MAX_HINTS)
+        .map(|(_, hint)| hint)
+        .collect()
+}
+
+fn hint(view: &View, action: RegicideAction, cards: &[u8], reasons: Vec<String>) -> Hint {
+    Hint {
+        action,
+        cards: cards
+            .iter()
+            .map(|i| JsCard::from(view.hand[*i as usize]))
+            .collect(),
+        reasons,
+    }
+}
+
+fn strength(cards: impl Iterator<Item = Card>) -> u16 {
+    cards.map(|card| card.strength() as u16).sum()
+}
+
+fn play_hints(view: &View) -> Vec<(i32, Hint)> {
+    let enemy = view.enemy;
+    let health = view.health();
+    plays(view.hand)
+        .into_iter()
+        .map(|(cards, combo)| {
+            let mut reasons = Vec::new();
+            let mut score;
+            if matches!(combo, Combo::Jester) {
+                reasons.push(if view.immune_to_spades() {
+                    format!("The jester cancels {enemy}'s immunity to spades and skips its attack")
+                } else {
+                    format!("The jester skips {enemy}'s attack")
+                });
+                reasons.push("You choose who plays next".to_string());
+                score = if view.immune_to_spades() { 300 } else { 50 };
+            } else {
+                let dealt = combo.strength();
+                if dealt == health {
+                    reasons.push(format!("Exact kill puts {enemy} on top of the library"));
+                    score = 1000;
+                } else if dealt > health {
+                    reasons.push(format!(
+                        "Defeats {enemy}, overkilling by {}",
+                        dealt - health
+                    ));
+                    score = 900 - 5 * (dealt - health) as i32;
+                } else {
+                    reasons.push(format!("Deals {dealt} of the {health} damage needed"));
+                    score = 10 * dealt as i32;
+                }
+                // A spade royal ignores spades until a jester is played; the
+                // other suits always work.
+                let power = |suit: Suit| {
+                    if suit == Suit::Spade && view.immune_to_spades() {
+                        0
+                    } else {
+                        combo.suit_value(suit)
+                    }
+                };
+                if combo.suit_value(Suit::Spade) > 0 && power(Suit::Spade) == 0 {
+                    reasons.push(format!("{enemy} is immune to spades"));
+                }
+                if power(Suit::Club) > 0 {
+                    reasons.push("Clubs deal double damage".to_string());
+                }
+                let heal = (power(Suit::Heart) as usize).min(view.discard);
+                if heal > 0 {
+                    reasons.push(format!(
+                        "Hearts heal {heal} cards from the discard into the library"
+                    ));
+                    score += 3 * heal as i32;
+                }
+                let draw = power(Suit::Diamond);
+                if draw > 0 {
+                    reasons.push(format!("Diamonds refill hands with {draw} draws"));
+                    score += 4 * draw as i32;
+                }
+                let spades = power(Suit::Spade);
+                if spades > 0 {
+                    reasons.push(format!("Spades cut {enemy}'s attack by {spades}"));
+                    score += 5 * spades.min(view.attack()) as i32;
+                }
+                if dealt < health {
+                    let attack = view.attack().saturating_sub(spades) as u16;
+                    let left = (0..view.hand.len() as u8)
+                        .filter(|i| !cards.contains(i))
+                        .map(|i| view.hand[i as usize]);
+                    if strength(left) < attack {
+                        reasons.push(format!(
+                            "Leaves too little to block {attack} damage without draws"
+                        ));
+                        score -= 500;
+                    }
+                }
+                score -= cards.len() as i32;
+            }
+            let action = RegicideAction::Play {
+                cards: cards.clone(),
+            };
+            (score, hint(view, action, &cards, reasons))
+        })
+        .collect()
+}
+
+fn defend_hints(view: &View) -> Vec<(i32, Hint)> {
+    let need = view.attack();
+    let covers = defenses(view.hand, need);
+    if covers.is_empty() {
+        let cards: ArrayVec<u8, 8> = (0..view.hand.len() as u8).collect();
+        let reasons = vec![format!(
+            "Nothing in hand blocks {need} damage; the party will fall"
+        )];
+        let action = RegicideAction::Discard {
+            cards: cards.clone(),
+        };
+        return vec![(0, hint(view, action, &cards, reasons))];
+    }
+    covers
+        .into_iter()
+        .map(|cards| {
+            let blocked = strength(cards.iter().map(|i| view.hand[*i as usize]));
+            let kept = view.hand.len() - cards.len();
+            let mut reasons = vec![format!("Blocks {need} damage with {blocked}")];
+            if blocked > need as u16 {
+                reasons.push(format!("Wastes {}", blocked - need as u16));
+            }
+            reasons.push(format!("Keeps {kept} cards in hand"));
+            let score = -(blocked as i32) * 10 + kept as i32;
+            let action = RegicideAction::Discard {
+                cards: cards.clone(),
+            };
+            (score, hint(view, action, &cards, reasons))
+        })
+        .collect()
+}
+
+fn jester_hints(view: &View) -> Vec<(i32, Hint)> {
+    view.players
+        .iter()
+        .map(|(player, cards)| {
+            let mut reasons = vec![format!("{player} holds {cards} cards")];
+            if *player == view.user_id {
+                reasons.push("You keep the initiative".to_string());
+            }
+            let action = RegicideAction::Jester { player: *player };
+            (*cards as i32, hint(view, action, &[], reasons))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actor::UserId;
+
+    fn view(hand: &[Card], enemy: Card, jester: bool) -> View<'_> {
+        let user_id = UserId::from("ann").unwrap();
+        View {
+            user_id,
+            phase: Phase::Play(user_id),
+            hand,
+            enemy,
+            damage: 0,
+            jester,
+            shield: 0,
+            library: 20,
+            discard: 10,
+            players: vec![(user_id, hand.len())],
+        }
+    }
+
+    fn reasons(view: &View, card: Card) -> Vec<String> {
+        hints(view)
+            .into_iter()
+            .find(|hint| hint.cards.len() == 1 && hint.cards[0].value == card.value())
+            .map(|hint| hint.reasons)
+            .expect("hint for the card")
+    }
+
+    #[test]
+    fn a_spade_royal_blocks_spades() {
+        let hand = [Card::SpadeFive];
+        let reasons = reasons(&view(&hand, Card::SpadeJack, false), Card::SpadeFive);
+        assert!(reasons.contains(&"SpadeJack is immune to spades".to_string()));
+        assert!(!reasons.iter().any(|r| r.starts_with("Spades")));
+    }
+
+    #[test]
+    fn other_royals_block_nothing() {
+        // The engine doubles clubs and heals with hearts whatever the enemy.
+        let hand = [Card::ClubFive];
+        let clubs = reasons(&view(&hand, Card::ClubJack, false), Card::ClubFive);
+        assert!(clubs.contains(&"Clubs deal double damage".to_string()));
+        assert!(clubs.contains(&"Deals 10 of the 20 damage needed".to_string()));
+        assert!(!clubs.iter().any(|r| r.contains("immune")));
+
+        let hand = [Card::HeartFour];
+        let hearts = reasons(&view(&hand, Card::HeartQueen, false), Card::HeartFour);
+        assert!(hearts.iter().any(|r| r.starts_with("Hearts")));
+    }
+
+    #[test]
+    fn a_jester_lifts_the_immunity() {
+        let hand = [Card::SpadeFive];
+        let reasons = reasons(&view(&hand, Card::SpadeJack, true), Card::SpadeFive);
+        assert!(reasons.contains(&"Spades cut SpadeJack's attack by 5".to_string()));
+        assert!(!reasons.iter().any(|r| r.contains("immune")));
+    }
+
+    #[test]
+    fn other_suits_keep_their_reasons() {
+        let hand = [Card::DiamondThree];
+        let reasons = reasons(&view(&hand, Card::ClubJack, false), Card::DiamondThree);
+        assert!(reasons.contains(&"Diamonds refill hands with 3 draws".to_string()));
+    }
+}
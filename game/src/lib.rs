@@ -1,13 +1,19 @@
 pub mod action;
+pub mod bots;
 pub mod card;
 pub mod deck;
+pub mod hint;
 pub mod msg;
 pub mod phase;
+pub mod rules;
 pub mod state;
 
 pub use action::*;
+pub use bots::*;
 pub use card::*;
 pub use deck::*;
+pub use hint::*;
 pub use msg::*;
 pub use phase::*;
+pub use rules::*;
 pub use state::*;
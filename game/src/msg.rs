@@ -1,7 +1,97 @@
 use crate::RegicideAction;
 use actor::{Action, ActorId, UserId};
-use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use ts_rs::TS;
+use utoipa::ToSchema;
+
+/// WebSocket endpoint speaking [`ClientMsg`] and `ServerMsg`.
+pub const WS_HANDLER_PATH: &str = "/ws";
+/// Server-sent events fallback for networks that block WebSockets.
+pub const EVENTS_PATH: &str = "/events";
+/// Where an events session posts its `ClientMsg`s, by the id it was given.
+pub const EVENTS_SEND_PATH: &str = "/events/{session}";
+/// Open lobbies as JSON `[actor_id, players]` pairs.
+pub const ACTOR_LIST_PATH: &str = "/lobbies";
+/// Server-sent `LobbyEvent<Lobby>`s as lobbies open, fill and close.
+pub const LOBBY_EVENTS_PATH: &str = "/lobbies/events";
+
+/// Revision of the `ClientMsg`/`ServerMsg` wire format. Bump it on any change
+/// to those enums or the types they carry.
+pub const PROTOCOL_VERSION: u16 = 5;
+/// Oldest peer revision this build can still talk to. Appending enum variants
+/// keeps older peers working; any other change must raise this to
+/// [`PROTOCOL_VERSION`], which turns stale peers away instead of letting them
+/// mis-decode.
+pub const MIN_PROTOCOL_VERSION: u16 = 4;
+
+/// The revision a peer speaks and the oldest revision it accepts.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ProtocolVersion {
+    pub version: u16,
+    pub min_version: u16,
+}
+
+impl ProtocolVersion {
+    pub const CURRENT: Self = Self {
+        version: PROTOCOL_VERSION,
+        min_version: MIN_PROTOCOL_VERSION,
+    };
+    /// Clients from before the handshake, which join without a hello.
+    pub const LEGACY: Self = Self {
+        version: 0,
+        min_version: 0,
+    };
+    /// Each side must be at least as new as the other's minimum.
+    pub fn compatible(self, peer: Self) -> bool {
+        peer.version >= self.min_version && self.version >= peer.min_version
+    }
+}
+
+/// How frames on a game socket are encoded. Chosen when the socket opens, by
+/// `Sec-WebSocket-Protocol` or else the `encoding` query parameter.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize, TS, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[ts(export, export_to = "../../frontend/src/bindings/")]
+pub enum Encoding {
+    /// Binary postcard frames, as the bundled frontend sends.
+    #[default]
+    Postcard,
+    /// JSON text frames, readable in devtools and from `websocat`.
+    Json,
+}
+
+impl Encoding {
+    pub const ALL: [Self; 2] = [Self::Postcard, Self::Json];
+    /// The WebSocket subprotocol that selects this encoding.
+    pub fn protocol(self) -> &'static str {
+        match self {
+            Self::Postcard => "regicide.postcard",
+            Self::Json => "regicide.json",
+        }
+    }
+    pub fn from_protocol(protocol: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|encoding| encoding.protocol() == protocol)
+    }
+}
+
+/// An open lobby as listed to players looking for a game.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize, TS, ToSchema)]
+#[ts(export, export_to = "../../frontend/src/bindings/")]
+pub struct Lobby {
+    pub players: u8,
+    pub min_players: u8,
+    pub max_players: u8,
+    /// House rule for jesters; `None` follows the player count.
+    pub jesters: Option<u8>,
+}
+
+impl Lobby {
+    pub fn open_seats(&self) -> u8 {
+        self.max_players.saturating_sub(self.players)
+    }
+}
 
 #[derive(Serialize, Deserialize, TS, Clone, Debug)]
 #[serde(bound = "A: Serialize + DeserializeOwned")]
@@ -16,4 +106,19 @@ pub enum ClientMsg<A: Action> {
     Action {
         action: A,
     },
+    /// Ask for suggested moves; answered with a hint message.
+    RequestHint,
+    /// Sent before `Join` to announce the client's protocol revision. Clients
+    /// that skip it are treated as revision 0.
+    Hello {
+        version: u16,
+        min_version: u16,
+    },
+    /// Instead of `Join`: wait for others with the same preference and be
+    /// seated together once there are enough.
+    QuickMatch {
+        #[ts(as = "String")]
+        client_token: UserId,
+        preference: A::Preference,
+    },
 }
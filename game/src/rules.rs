@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+pub const MAX_PLAYERS: usize = 4;
+const MAX_JESTERS: u8 = 2;
+
+/// House rules for a lobby, fixed when the lobby is created.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Ruleset {
+    /// Players needed before the game can be started.
+    pub min_players: u8,
+    /// Seats in the lobby.
+    pub max_players: u8,
+    /// Jesters shuffled into the library, `None` to follow the player count.
+    pub jesters: Option<u8>,
+    /// Seeds the shuffle so deals are reproducible. Unset in normal play.
+    pub seed: Option<u64>,
+}
+
+impl Default for Ruleset {
+    fn default() -> Self {
+        Self {
+            min_players: MAX_PLAYERS as u8,
+            max_players: MAX_PLAYERS as u8,
+            jesters: None,
+            seed: None,
+        }
+    }
+}
+
+/// What a player asks of a quick match. Players with equal preferences are
+/// seated together.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../frontend/src/bindings/")]
+pub struct MatchPreference {
+    pub players: u8,
+    /// House rule for jesters; `None` follows the player count.
+    pub jesters: Option<u8>,
+}
+
+impl Ruleset {
+    /// These rules at the table size and house rules a quick match asked for.
+    pub fn for_match(&self, preference: &MatchPreference) -> Result<Self, String> {
+        let rules = Self {
+            min_players: preference.players,
+            max_players: preference.players,
+            jesters: preference.jesters,
+            ..*self
+        };
+        rules.validate()?;
+        Ok(rules)
+    }
+    pub fn validate(&self) -> Result<(), String> {
+        if self.min_players == 0 {
+            return Err("min_players must be at least 1".to_string());
+        }
+        if self.max_players as usize > MAX_PLAYERS {
+            return Err(format!("max_players must be at most {MAX_PLAYERS}"));
+        }
+        if self.min_players > self.max_players {
+            return Err(format!(
+                "min_players ({}) must not exceed max_players ({})",
+                self.min_players, self.max_players
+            ));
+        }
+        if self.jesters.is_some_and(|j| j > MAX_JESTERS) {
+            return Err(format!("jesters must be at most {MAX_JESTERS}"));
+        }
+        Ok(())
+    }
+    pub fn jesters(&self, players: usize) -> usize {
+        self.jesters
+            .map(usize::from)
+            .unwrap_or(players.saturating_sub(MAX_JESTERS as usize))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_match_fixes_the_table_size_and_keeps_the_rest() {
+        let rules = Ruleset {
+            seed: Some(3),
+            ..Ruleset::default()
+        };
+        let preference = MatchPreference {
+            players: 3,
+            jesters: Some(1),
+        };
+        let matched = rules.for_match(&preference).unwrap();
+        assert_eq!((matched.min_players, matched.max_players), (3, 3));
+        assert_eq!(matched.jesters, Some(1));
+        assert_eq!(matched.seed, Some(3));
+    }
+
+    #[test]
+    fn impossible_matches_are_refused() {
+        let rules = Ruleset::default();
+        let players = |players| MatchPreference {
+            players,
+            jesters: None,
+        };
+        assert!(rules.for_match(&players(0)).is_err());
+        assert!(rules.for_match(&players(MAX_PLAYERS as u8 + 1)).is_err());
+        let jesters = MatchPreference {
+            players: 2,
+            jesters: Some(MAX_JESTERS + 1),
+        };
+        assert!(rules.for_match(&jesters).is_err());
+    }
+}
@@ -1,39 +1,96 @@
-use crate::{Card, Deck, JsCard, Phase};
+use crate::{Card, Deck, Difficulty, Hint, JsCard, MAX_PLAYERS, Phase, Ruleset};
 use actor::{ActorId, UserId};
 use arrayvec::ArrayVec;
-use rand::rngs::ThreadRng;
+use rand::{SeedableRng, rngs::StdRng};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use ts_rs::TS;
 use utoipa::ToSchema;
 
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+pub struct SharedState {
+    pub rules: Ruleset,
+    pub game: GameState,
+    /// The first human to join; only they may add or remove bots.
+    pub host: Option<UserId>,
+    pub bots: ArrayVec<(UserId, Difficulty), MAX_PLAYERS>,
+    /// Round-trip time of each connected player, in milliseconds. Only
+    /// meaningful while the connections last, so never snapshotted.
+    #[serde(skip)]
+    pub latency: HashMap<UserId, u32>,
+    /// Players with a connection open, the only ones a rematch waits for.
+    /// Everyone starts out away after a restart, until they reconnect.
+    #[serde(skip)]
+    pub connected: HashSet<UserId>,
+}
+
+impl SharedState {
+    pub fn new(rules: Ruleset) -> Self {
+        Self {
+            rules,
+            game: GameState::Uninit,
+            host: None,
+            bots: ArrayVec::new(),
+            latency: HashMap::new(),
+            connected: HashSet::new(),
+        }
+    }
+}
+
 #[allow(clippy::large_enum_variant)]
-#[derive(Clone, Default, Debug)]
-pub enum SharedState {
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+pub enum GameState {
     #[default]
     Uninit,
     Init {
         phase: Phase,
         deck: Deck,
-        turn_order: [UserId; 4],
+        turn_order: ArrayVec<UserId, MAX_PLAYERS>,
         damage: u8,
+        /// Players who have yielded in a row.
+        yields: u8,
+        /// Players who voted to play again once the game is over, and whether
+        /// they asked to move first turn along.
+        rematch: ArrayVec<(UserId, bool), MAX_PLAYERS>,
+        stats: GameStats,
+        /// What the deck was shuffled from; with the recorded actions it
+        /// plays the game out again.
+        seed: u64,
+        /// How many games were dealt at this table before this one.
+        number: u32,
     },
 }
 
-impl SharedState {
-    pub fn init(&mut self, jesters: usize, turn_order: [UserId; 4], rng: ThreadRng) {
-        let deck = Deck::new(jesters, rng);
+impl GameState {
+    pub fn init(
+        &mut self,
+        jesters: usize,
+        turn_order: ArrayVec<UserId, MAX_PLAYERS>,
+        seed: u64,
+        number: u32,
+    ) {
+        let deck = Deck::new(jesters, StdRng::seed_from_u64(seed));
         let phase = Phase::Play(turn_order[0]);
         let damage = 0;
+        let stats = GameStats {
+            damage: turn_order.iter().map(|id| (*id, 0)).collect(),
+            ..GameStats::default()
+        };
         *self = Self::Init {
             deck,
             phase,
             turn_order,
             damage,
+            yields: 0,
+            rematch: ArrayVec::new(),
+            stats,
+            seed,
+            number,
         };
     }
 }
 
-#[derive(Clone, Default, Debug)]
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
 pub enum UserState {
     #[default]
     Uninit,
@@ -59,7 +116,51 @@ impl UserState {
     }
 }
 
-#[derive(Serialize, Deserialize, TS, ToSchema)]
+/// Running totals for one game.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, TS, ToSchema)]
+#[ts(export, export_to = "../../frontend/src/bindings/")]
+pub struct GameStats {
+    /// Plays and yields; defending and picking after a jester are part of
+    /// the turn they follow.
+    pub turns: u16,
+    /// Damage each player dealt, overkill included, in turn order.
+    #[ts(as = "Vec<(String, u16)>")]
+    #[schema(value_type = Vec<(String, u16)>)]
+    pub damage: ArrayVec<(UserId, u16), MAX_PLAYERS>,
+    /// Cards hearts shuffled back from the discard pile into the library.
+    pub healed: u16,
+    /// Cards diamonds dealt out.
+    pub drawn: u16,
+    /// Enemies beaten with exactly their health, which sends them to the
+    /// top of the library.
+    pub exact_kills: u8,
+    pub jesters: u8,
+}
+
+impl GameStats {
+    pub fn dealt(&mut self, user_id: UserId, damage: u8) {
+        if let Some((_, total)) = self.damage.iter_mut().find(|(id, _)| *id == user_id) {
+            *total += damage as u16;
+        }
+    }
+}
+
+/// How a finished game went. Sent to the table and kept with the game in
+/// each player's history.
+#[derive(Clone, Debug, Serialize, Deserialize, TS, ToSchema)]
+#[ts(export, export_to = "../../frontend/src/bindings/")]
+pub struct Outcome {
+    pub victory: bool,
+    /// Royals beaten, out of twelve.
+    pub enemies_defeated: u8,
+    /// The enemy the party fell to.
+    pub defeated_by: Option<JsCard>,
+    /// Missing from games recorded before stats were kept.
+    #[serde(default)]
+    pub stats: GameStats,
+}
+
+#[derive(Clone, Serialize, Deserialize, TS, ToSchema)]
 #[ts(export, export_to = "../../frontend/src/bindings/")]
 pub enum ServerMsg {
     Join {
@@ -71,6 +172,11 @@ pub enum ServerMsg {
         #[ts(as = "Vec<(String, u8)>")]
         #[schema(value_type = Vec<(String, u8)>)]
         players: Vec<(UserId, u8)>,
+        /// Round-trip milliseconds of connected players, in turn order.
+        /// Bots and offline players are left out.
+        #[ts(as = "Vec<(String, u32)>")]
+        #[schema(value_type = Vec<(String, u32)>)]
+        latency: Vec<(UserId, u32)>,
         library_size: u8,
         discard_size: u8,
         damage: u8,
@@ -78,6 +184,67 @@ pub enum ServerMsg {
         hand: Vec<JsCard>,
         resolving: Vec<Vec<JsCard>>,
     },
-    Victory,
-    Defeat,
+    /// The game is over. `rematch` lists who has voted to play again, out of
+    /// the `seats` whose votes are needed. Only peers from before
+    /// [`ServerMsg::Finished`] are sent these.
+    Victory {
+        #[ts(as = "Vec<String>")]
+        #[schema(value_type = Vec<String>)]
+        rematch: Vec<UserId>,
+        seats: u8,
+    },
+    Defeat {
+        #[ts(as = "Vec<String>")]
+        #[schema(value_type = Vec<String>)]
+        rematch: Vec<UserId>,
+        seats: u8,
+    },
+    ServerShutdown {
+        reconnect_after: u32,
+    },
+    /// Answer to `ClientMsg::RequestHint`, best suggestion first.
+    Hint {
+        hints: Vec<Hint>,
+    },
+    /// The client's protocol revision cannot talk to this server. The socket
+    /// closes right after.
+    IncompatibleVersion {
+        version: u16,
+        min_version: u16,
+    },
+    /// Waiting for a quick match: this player's place in the queue, how many
+    /// are waiting and how many the lobby needs.
+    Queued {
+        position: u8,
+        waiting: u8,
+        players: u8,
+    },
+    /// The game is over, with how it went. `rematch` lists who has voted to
+    /// play again, out of the `seats` whose votes are needed.
+    Finished {
+        outcome: Outcome,
+        #[ts(as = "Vec<String>")]
+        #[schema(value_type = Vec<String>)]
+        rematch: Vec<UserId>,
+        seats: u8,
+    },
+}
+
+impl ServerMsg {
+    /// Rewrites a message for a peer speaking protocol `version`, in the
+    /// shape that revision knows.
+    pub fn downgrade(self, version: u16) -> Self {
+        match self {
+            // `Finished` replaced `Victory` and `Defeat` in revision 5.
+            Self::Finished {
+                outcome,
+                rematch,
+                seats,
+            } if version < 5 => match outcome.victory {
+                true => Self::Victory { rematch, seats },
+                false => Self::Defeat { rematch, seats },
+            },
+            msg => msg,
+        }
+    }
 }
@@ -0,0 +1,225 @@
+use actor::UserId;
+use client::{Error, RegicideClient};
+use futures_util::{StreamExt, future::join_all};
+use game::{Card, JsCard, Phase, RegicideAction, ServerMsg, cheapest_defense, plays};
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::IndexedRandom};
+use std::time::Duration;
+use tokio::time::{Instant, interval, sleep, timeout_at};
+
+/// How long an action may go unanswered before it counts as a timeout.
+const ACTION_TIMEOUT: Duration = Duration::from_secs(5);
+/// How often the first seat retries starting the game.
+const START_EVERY: Duration = Duration::from_millis(250);
+
+/// What a group of seats saw over a run.
+#[derive(Clone, Debug, Default)]
+pub struct GroupStats {
+    /// Time from sending an action until the table reflected it.
+    pub latencies: Vec<Duration>,
+    pub actions: usize,
+    /// Actions the table never reflected, usually because they were illegal.
+    pub timeouts: usize,
+    pub msgs: usize,
+    pub games: usize,
+    pub victories: usize,
+    /// Games cut off by the deadline.
+    pub unfinished: usize,
+    /// Rounds that failed to connect or join.
+    pub errors: usize,
+}
+
+impl GroupStats {
+    pub fn merge(&mut self, other: GroupStats) {
+        self.latencies.extend(other.latencies);
+        self.actions += other.actions;
+        self.timeouts += other.timeouts;
+        self.msgs += other.msgs;
+        self.games += other.games;
+        self.victories += other.victories;
+        self.unfinished += other.unfinished;
+        self.errors += other.errors;
+    }
+}
+
+/// Seats `players` clients in a fresh lobby and plays random legal moves,
+/// game after game, until `deadline`.
+pub async fn run_group(
+    server: String,
+    group: usize,
+    players: u8,
+    think: Duration,
+    deadline: Instant,
+    seed: u64,
+) -> GroupStats {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut stats = GroupStats::default();
+    let mut round = 0;
+    while Instant::now() < deadline {
+        match play_round(&server, group, round, players, think, deadline, &mut rng).await {
+            Ok((seats, outcome)) => {
+                stats.merge(seats);
+                match outcome {
+                    Some(victory) => {
+                        stats.games += 1;
+                        stats.victories += victory as usize;
+                    }
+                    None => stats.unfinished += 1,
+                }
+            }
+            Err(_) => {
+                stats.errors += 1;
+                sleep(START_EVERY).await;
+            }
+        }
+        round += 1;
+    }
+    stats
+}
+
+async fn play_round(
+    server: &str,
+    group: usize,
+    round: usize,
+    players: u8,
+    think: Duration,
+    deadline: Instant,
+    rng: &mut StdRng,
+) -> Result<(GroupStats, Option<bool>), Error> {
+    let mut seats = Vec::new();
+    let mut lobby = None;
+    for seat in 0..players {
+        let token = UserId::from(&format!("load-{group}-{round}-{seat}")).expect("token fits");
+        let mut client = RegicideClient::new(server)?;
+        client.join(lobby, token).await?;
+        // Wait until seated, so the first seat cannot start without us.
+        let joined = loop {
+            match timeout_at(deadline, client.next()).await {
+                Ok(Some(ServerMsg::Join { joined })) => break joined,
+                Ok(Some(_)) => continue,
+                Ok(None) | Err(_) => return Ok((GroupStats::default(), None)),
+            }
+        };
+        if lobby.is_some_and(|lobby| lobby != joined) {
+            return Err(Error::NotJoined);
+        }
+        lobby = Some(joined);
+        seats.push((client, token));
+    }
+    let results = join_all(
+        seats
+            .into_iter()
+            .enumerate()
+            .map(|(seat, (client, token))| {
+                let rng = StdRng::seed_from_u64(rng.random());
+                play_seat(client, token, seat == 0, think, deadline, rng)
+            }),
+    )
+    .await;
+    let mut stats = GroupStats::default();
+    let mut outcome = None;
+    results.into_iter().for_each(|(seat, seat_outcome)| {
+        stats.merge(seat);
+        outcome = outcome.or(seat_outcome);
+    });
+    Ok((stats, outcome))
+}
+
+async fn play_seat(
+    mut client: RegicideClient,
+    me: UserId,
+    host: bool,
+    think: Duration,
+    deadline: Instant,
+    mut rng: StdRng,
+) -> (GroupStats, Option<bool>) {
+    let mut stats = GroupStats::default();
+    let mut started = false;
+    let mut start = interval(START_EVERY);
+    // When our last action was sent and the table as it stood then.
+    let mut pending: Option<(Instant, Vec<u8>)> = None;
+    loop {
+        let msg = tokio::select! {
+            msg = timeout_at(deadline, client.next()) => match msg {
+                Ok(Some(msg)) => msg,
+                Ok(None) | Err(_) => return (stats, None),
+            },
+            _ = start.tick(), if host && !started => {
+                let _ = client.send(RegicideAction::Init);
+                continue;
+            }
+        };
+        stats.msgs += 1;
+        let ended = match &msg {
+            ServerMsg::Game { .. } => None,
+            ServerMsg::Finished { outcome, .. } => Some(outcome.victory),
+            ServerMsg::Victory { .. } => Some(true),
+            ServerMsg::Defeat { .. } => Some(false),
+            _ => continue,
+        };
+        started = true;
+        let mut msg = msg;
+        if let ServerMsg::Game { latency, .. } = &mut msg {
+            // Heartbeats update this without anyone acting.
+            latency.clear();
+        }
+        let table = postcard::to_stdvec(&msg).unwrap_or_default();
+        if let Some((sent, before)) = &pending {
+            if *before != table {
+                stats.latencies.push(sent.elapsed());
+                pending = None;
+            } else if sent.elapsed() > ACTION_TIMEOUT {
+                stats.timeouts += 1;
+                pending = None;
+            }
+        }
+        if ended.is_some() {
+            return (stats, ended);
+        }
+        if pending.is_some() {
+            continue;
+        }
+        if let Some(action) = choose(&msg, me, &mut rng) {
+            if !think.is_zero() {
+                sleep(think).await;
+            }
+            if client.send(action).is_err() {
+                return (stats, None);
+            }
+            stats.actions += 1;
+            pending = Some((Instant::now(), table));
+        }
+    }
+}
+
+/// A random legal move for `me`, or `None` when it is not our turn.
+pub fn choose(msg: &ServerMsg, me: UserId, rng: &mut impl Rng) -> Option<RegicideAction> {
+    let ServerMsg::Game {
+        phase,
+        players,
+        enemy,
+        hand,
+        ..
+    } = msg
+    else {
+        return None;
+    };
+    let hand: Vec<Card> = hand.iter().filter_map(JsCard::card).collect();
+    match *phase {
+        Phase::Play(id) if id == me => {
+            // No legal play means an empty hand, so yield.
+            let cards = plays(&hand)
+                .choose(rng)
+                .map(|(cards, _)| cards.clone())
+                .unwrap_or_default();
+            Some(RegicideAction::Play { cards })
+        }
+        // Spades already in play may lower the attack, so this can overpay.
+        Phase::Defend(id) if id == me => Some(RegicideAction::Discard {
+            cards: cheapest_defense(&hand, enemy.value),
+        }),
+        Phase::Jester(id) if id == me => players
+            .choose(rng)
+            .map(|(player, _)| RegicideAction::Jester { player: *player }),
+        _ => None,
+    }
+}
@@ -0,0 +1,7 @@
+pub mod group;
+pub mod report;
+pub mod server;
+
+pub use group::*;
+pub use report::*;
+pub use server::*;
@@ -0,0 +1,117 @@
+use clap::Parser;
+use futures_util::future::join_all;
+use game::MAX_PLAYERS;
+use loadtest::{GroupStats, Report, cpu_seconds, run_group, scrape};
+use std::{collections::BTreeMap, process::ExitCode, time::Duration};
+use tokio::time::Instant;
+
+/// Plays random legal moves from many clients against a running server and
+/// reports latency, dropped messages and server CPU.
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    /// Base URL of the server.
+    #[arg(short, long, default_value = "http://127.0.0.1:3000")]
+    server: String,
+    /// Simulated clients, seated in lobbies of `--players`.
+    #[arg(short, long, default_value_t = 64)]
+    clients: usize,
+    /// Players per lobby. The server's `min_players` must not be higher.
+    #[arg(short, long, default_value_t = 2,
+        value_parser = clap::value_parser!(u8).range(1..=MAX_PLAYERS as i64))]
+    players: u8,
+    /// How long to run.
+    #[arg(long, default_value_t = 30)]
+    seconds: u64,
+    /// Delay before each move.
+    #[arg(long, default_value_t = 0)]
+    think_ms: u64,
+    /// Server process to sample CPU time from.
+    #[arg(long)]
+    pid: Option<u32>,
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+    /// Print the report as JSON.
+    #[arg(long)]
+    json: bool,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let groups = cli.clients / cli.players as usize;
+    if groups == 0 {
+        eprintln!("error: need at least {} clients", cli.players);
+        return ExitCode::FAILURE;
+    }
+    let before = match scrape(&cli.server).await {
+        Ok(counters) => counters,
+        Err(err) => {
+            eprintln!("error: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let cpu_before = cli.pid.and_then(cpu_seconds);
+    let start = Instant::now();
+    let deadline = start + Duration::from_secs(cli.seconds);
+    let think = Duration::from_millis(cli.think_ms);
+    let handles: Vec<_> = (0..groups)
+        .map(|group| {
+            let seed = cli.seed.wrapping_add(group as u64);
+            tokio::spawn(run_group(
+                cli.server.clone(),
+                group,
+                cli.players,
+                think,
+                deadline,
+                seed,
+            ))
+        })
+        .collect();
+    let mut stats = GroupStats::default();
+    join_all(handles)
+        .await
+        .into_iter()
+        .flatten()
+        .for_each(|group| stats.merge(group));
+    let elapsed = start.elapsed();
+
+    let cpu = cli
+        .pid
+        .and_then(cpu_seconds)
+        .zip(cpu_before)
+        .map(|(after, before)| after - before);
+    let server: BTreeMap<String, f64> = match scrape(&cli.server).await {
+        Ok(after) => after
+            .into_iter()
+            .map(|(name, value)| {
+                let delta = value - before.get(&name).copied().unwrap_or_default();
+                (name, delta)
+            })
+            .collect(),
+        Err(err) => {
+            eprintln!("warning: failed to read server metrics: {err}");
+            BTreeMap::new()
+        }
+    };
+    let report = Report::new(
+        groups * cli.players as usize,
+        cli.players,
+        elapsed,
+        stats,
+        server,
+        cpu,
+    );
+    if cli.json {
+        match serde_json::to_string_pretty(&report) {
+            Ok(json) => println!("{json}"),
+            Err(err) => {
+                eprintln!("error: {err}");
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        print!("{report}");
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,126 @@
+use crate::GroupStats;
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt, time::Duration};
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Percentiles {
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+impl Percentiles {
+    /// Milliseconds at each percentile, nearest rank.
+    pub fn new(samples: &mut [Duration]) -> Self {
+        samples.sort();
+        let at = |p: f64| {
+            samples
+                .get(((samples.len() as f64 * p).ceil() as usize).saturating_sub(1))
+                .map_or(0.0, |d| d.as_secs_f64() * 1000.0)
+        };
+        Self {
+            p50: at(0.5),
+            p90: at(0.9),
+            p99: at(0.99),
+            max: at(1.0),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Report {
+    pub clients: usize,
+    pub players: u8,
+    pub seconds: f64,
+    pub games: usize,
+    pub victories: usize,
+    pub unfinished: usize,
+    pub errors: usize,
+    pub actions: usize,
+    pub timeouts: usize,
+    pub msgs: usize,
+    /// Action to table update, in milliseconds.
+    pub latency_ms: Percentiles,
+    /// Change in each server counter over the run.
+    pub server: BTreeMap<String, f64>,
+    /// Server CPU time over the run, when its pid was given.
+    pub cpu_seconds: Option<f64>,
+    pub cpu_ms_per_game: Option<f64>,
+}
+
+impl Report {
+    pub fn new(
+        clients: usize,
+        players: u8,
+        elapsed: Duration,
+        mut stats: GroupStats,
+        server: BTreeMap<String, f64>,
+        cpu_seconds: Option<f64>,
+    ) -> Self {
+        let games = stats.games + stats.unfinished;
+        Self {
+            clients,
+            players,
+            seconds: elapsed.as_secs_f64(),
+            games: stats.games,
+            victories: stats.victories,
+            unfinished: stats.unfinished,
+            errors: stats.errors,
+            actions: stats.actions,
+            timeouts: stats.timeouts,
+            msgs: stats.msgs,
+            latency_ms: Percentiles::new(&mut stats.latencies),
+            server,
+            cpu_seconds,
+            cpu_ms_per_game: cpu_seconds
+                .filter(|_| games > 0)
+                .map(|cpu| cpu * 1000.0 / games as f64),
+        }
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} clients in lobbies of {} for {:.1}s",
+            self.clients, self.players, self.seconds
+        )?;
+        writeln!(
+            f,
+            "games     {} finished ({} won), {} cut off, {} failed to join",
+            self.games, self.victories, self.unfinished, self.errors
+        )?;
+        writeln!(
+            f,
+            "actions   {} sent ({:.1}/s), {} unanswered",
+            self.actions,
+            self.actions as f64 / self.seconds,
+            self.timeouts
+        )?;
+        writeln!(
+            f,
+            "messages  {} received ({:.1}/s)",
+            self.msgs,
+            self.msgs as f64 / self.seconds
+        )?;
+        let l = &self.latency_ms;
+        writeln!(
+            f,
+            "latency   p50 {:.2}ms  p90 {:.2}ms  p99 {:.2}ms  max {:.2}ms",
+            l.p50, l.p90, l.p99, l.max
+        )?;
+        match (self.cpu_seconds, self.cpu_ms_per_game) {
+            (Some(cpu), Some(per_game)) => {
+                writeln!(f, "cpu       {cpu:.2}s, {per_game:.2}ms per game")?
+            }
+            (Some(cpu), None) => writeln!(f, "cpu       {cpu:.2}s")?,
+            _ => writeln!(f, "cpu       pass --pid to measure the server")?,
+        }
+        for (name, delta) in &self.server {
+            writeln!(f, "{name:<40} {delta:>10}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,50 @@
+use actor::telemetry::{
+    ACTIONS, SERVER_MSG_COALESCED, SERVER_MSG_DROPS, SERVER_MSG_FAILURES, SERVER_MSGS,
+};
+use backend::{METRICS_PATH, WS_SEND_FAILURES};
+use std::collections::BTreeMap;
+
+/// Server counters compared before and after a run.
+pub const COUNTERS: &[&str] = &[
+    ACTIONS,
+    SERVER_MSGS,
+    SERVER_MSG_DROPS,
+    SERVER_MSG_COALESCED,
+    SERVER_MSG_FAILURES,
+    WS_SEND_FAILURES,
+];
+
+/// Reads [`COUNTERS`] from the server's Prometheus endpoint, summed over labels.
+pub async fn scrape(server: &str) -> Result<BTreeMap<String, f64>, reqwest::Error> {
+    let url = format!("{}{METRICS_PATH}", server.trim_end_matches('/'));
+    let text = reqwest::get(url).await?.error_for_status()?.text().await?;
+    let mut counters: BTreeMap<String, f64> = COUNTERS
+        .iter()
+        .map(|name| (name.to_string(), 0.0))
+        .collect();
+    text.lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let name = line.split(['{', ' ']).next()?;
+            let value = line.rsplit(' ').next()?.parse::<f64>().ok()?;
+            Some((name, value))
+        })
+        .for_each(|(name, value)| {
+            if let Some(total) = counters.get_mut(name) {
+                *total += value;
+            }
+        });
+    Ok(counters)
+}
+
+/// User plus system CPU time of a local process, read from `/proc`.
+pub fn cpu_seconds(pid: u32) -> Option<f64> {
+    // The kernel reports these in USER_HZ, which is 100 on every Linux ABI.
+    const TICKS_PER_SECOND: f64 = 100.0;
+    let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    // Fields after the parenthesised command name, starting with the state.
+    let fields: Vec<&str> = stat.rsplit_once(") ")?.1.split_whitespace().collect();
+    let utime: f64 = fields.get(11)?.parse().ok()?;
+    let stime: f64 = fields.get(12)?.parse().ok()?;
+    Some((utime + stime) / TICKS_PER_SECOND)
+}
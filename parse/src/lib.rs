@@ -1,4 +1,4 @@
-use game::{ClientMsg, RegicideAction, ServerMsg};
+use game::{ClientMsg, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION, RegicideAction, ServerMsg};
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
@@ -17,3 +17,34 @@ pub fn serialize_payload(value: JsValue) -> Result<Box<[u8]>, JsValue> {
         .map_err(|e| JsValue::from_str(&format!("bincode encode failed: {e}")))?;
     Ok(bytes.into_boxed_slice())
 }
+
+/// Parses a JSON text frame into a `ServerMsg`, rejecting anything the
+/// server could not have sent.
+#[wasm_bindgen]
+pub fn deserialize_json(text: &str) -> Result<JsValue, JsValue> {
+    let payload: ServerMsg = serde_json::from_str(text)
+        .map_err(|e| JsValue::from_str(&format!("JSON decode failed: {e}")))?;
+    serde_wasm_bindgen::to_value(&payload)
+        .map_err(|e| JsValue::from_str(&format!("serde-wasm-bindgen encode failed: {e}")))
+}
+
+/// Checks a `ClientMsg` and renders it as a JSON text frame.
+#[wasm_bindgen]
+pub fn serialize_json(value: JsValue) -> Result<String, JsValue> {
+    let payload: ClientMsg<RegicideAction> = serde_wasm_bindgen::from_value(value)
+        .map_err(|e| JsValue::from_str(&format!("serde-wasm-bindgen decode failed: {e}")))?;
+    serde_json::to_string(&payload)
+        .map_err(|e| JsValue::from_str(&format!("JSON encode failed: {e}")))
+}
+
+/// Protocol revision this bundle speaks, for the `Hello` handshake.
+#[wasm_bindgen]
+pub fn protocol_version() -> u16 {
+    PROTOCOL_VERSION
+}
+
+/// Oldest server revision this bundle can talk to.
+#[wasm_bindgen]
+pub fn min_protocol_version() -> u16 {
+    MIN_PROTOCOL_VERSION
+}
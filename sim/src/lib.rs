@@ -0,0 +1,5 @@
+pub mod report;
+pub mod run;
+
+pub use report::*;
+pub use run::*;
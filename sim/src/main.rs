@@ -0,0 +1,93 @@
+use clap::Parser;
+use game::{Bot, Difficulty, Policy, Ruleset};
+use rand::{SeedableRng, rngs::StdRng};
+use sim::{Report, simulate};
+use std::{process::ExitCode, thread};
+
+/// Plays seeded Regicide games between bots and reports how they went.
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    /// Player counts to simulate.
+    #[arg(short, long, value_delimiter = ',', default_value = "1,2,3,4")]
+    players: Vec<u8>,
+    /// Games per player count.
+    #[arg(short, long, default_value_t = 1000)]
+    games: usize,
+    /// Seed of the first game; game `i` uses `seed + i`.
+    #[arg(short, long, default_value_t = 0)]
+    seed: u64,
+    /// Bot difficulty for every seat: easy, normal or hard.
+    #[arg(short, long, default_value = "hard", value_parser = parse_difficulty)]
+    difficulty: Difficulty,
+    /// Override the number of jesters in the library.
+    #[arg(long)]
+    jesters: Option<u8>,
+    /// Give up on a game after this many actions.
+    #[arg(long, default_value_t = 10_000)]
+    max_steps: usize,
+    /// Worker threads, defaults to the number of cores.
+    #[arg(long)]
+    threads: Option<usize>,
+    /// Print the report as JSON.
+    #[arg(long)]
+    json: bool,
+}
+
+fn parse_difficulty(s: &str) -> Result<Difficulty, String> {
+    match s.to_ascii_lowercase().as_str() {
+        "easy" => Ok(Difficulty::Easy),
+        "normal" => Ok(Difficulty::Normal),
+        "hard" => Ok(Difficulty::Hard),
+        _ => Err(format!("unknown difficulty `{s}`")),
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let rules = Ruleset {
+        jesters: cli.jesters,
+        ..Ruleset::default()
+    };
+    for &players in &cli.players {
+        let rules = Ruleset {
+            min_players: players,
+            max_players: players,
+            ..rules
+        };
+        if let Err(err) = rules.validate() {
+            eprintln!("error: {err}");
+            return ExitCode::FAILURE;
+        }
+    }
+    let threads = cli
+        .threads
+        .or_else(|| thread::available_parallelism().ok().map(Into::into))
+        .unwrap_or(1);
+    let difficulty = cli.difficulty;
+    let factory = move |_seat: usize, seed: u64| -> Box<dyn Policy> {
+        Box::new(Bot::with_rng(difficulty, StdRng::seed_from_u64(seed)))
+    };
+    let results = simulate(
+        rules,
+        &cli.players,
+        cli.games,
+        cli.seed,
+        threads,
+        cli.max_steps,
+        &factory,
+    );
+    let report = Report::new(&results);
+    if cli.json {
+        match serde_json::to_string_pretty(&report) {
+            Ok(json) => println!("{json}"),
+            Err(err) => {
+                eprintln!("error: {err}");
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        print!("{report}");
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,177 @@
+use crate::GameResult;
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt};
+
+/// Aggregated results for one player count.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Stats {
+    pub players: u8,
+    pub games: usize,
+    pub victories: usize,
+    /// Games that hit the step limit without finishing.
+    pub stalled: usize,
+    pub win_rate: f64,
+    pub avg_enemies_defeated: f64,
+    pub avg_jesters_played: f64,
+    pub avg_steps: f64,
+    /// How often each royal ended the party, most deadly first.
+    pub killers: Vec<(String, usize)>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Report {
+    pub stats: Vec<Stats>,
+}
+
+impl Report {
+    pub fn new(results: &[GameResult]) -> Self {
+        let mut by_players: BTreeMap<u8, Vec<&GameResult>> = BTreeMap::new();
+        results
+            .iter()
+            .for_each(|res| by_players.entry(res.players).or_default().push(res));
+        let stats = by_players
+            .into_iter()
+            .map(|(players, results)| {
+                let games = results.len();
+                let avg = |f: fn(&GameResult) -> usize| {
+                    results.iter().map(|res| f(res)).sum::<usize>() as f64 / games as f64
+                };
+                let victories = results.iter().filter(|r| r.victory == Some(true)).count();
+                let mut killers: BTreeMap<String, usize> = BTreeMap::new();
+                results
+                    .iter()
+                    .filter_map(|res| res.killer)
+                    .for_each(|card| *killers.entry(card.to_string()).or_default() += 1);
+                let mut killers: Vec<_> = killers.into_iter().collect();
+                killers.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+                Stats {
+                    players,
+                    games,
+                    victories,
+                    stalled: results.iter().filter(|r| r.victory.is_none()).count(),
+                    win_rate: victories as f64 / games as f64,
+                    avg_enemies_defeated: avg(|r| r.enemies_defeated as usize),
+                    avg_jesters_played: avg(|r| r.jesters_played as usize),
+                    avg_steps: avg(|r| r.steps),
+                    killers,
+                }
+            })
+            .collect();
+        Self { stats }
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>7} {:>7} {:>8} {:>8} {:>8} {:>8} {:>7}  deadliest",
+            "players", "games", "win %", "enemies", "jesters", "steps", "stalled"
+        )?;
+        for s in &self.stats {
+            let deadliest = s
+                .killers
+                .iter()
+                .take(3)
+                .map(|(card, n)| format!("{card} ({n})"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            writeln!(
+                f,
+                "{:>7} {:>7} {:>8.2} {:>8.2} {:>8.2} {:>8.1} {:>7}  {}",
+                s.players,
+                s.games,
+                s.win_rate * 100.0,
+                s.avg_enemies_defeated,
+                s.avg_jesters_played,
+                s.avg_steps,
+                s.stalled,
+                deadliest
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::run::play_out;
+    use game::{Bot, Difficulty, Outcome, Policy, Ruleset};
+    use rand::{SeedableRng, rngs::StdRng};
+    use std::ops::Range;
+
+    fn bots(_seat: usize, seed: u64) -> Box<dyn Policy> {
+        Box::new(Bot::with_rng(
+            Difficulty::Normal,
+            StdRng::seed_from_u64(seed),
+        ))
+    }
+
+    /// Seeded games, with how the engine summed each of them up.
+    fn games(players: u8, jesters: Option<u8>, seeds: Range<u64>) -> Vec<(GameResult, Outcome)> {
+        seeds
+            .map(|seed| {
+                let rules = Ruleset {
+                    min_players: players,
+                    max_players: players,
+                    jesters,
+                    seed: Some(seed),
+                };
+                let (result, outcome) = play_out(rules, players, &bots, 10_000);
+                (result, outcome.expect("bots finish every game"))
+            })
+            .collect()
+    }
+
+    fn mean(outcomes: &[&Outcome], f: fn(&Outcome) -> u8) -> f64 {
+        outcomes.iter().map(|o| f(o) as f64).sum::<f64>() / outcomes.len() as f64
+    }
+
+    #[test]
+    fn the_report_matches_the_games_it_sums_up() {
+        // Solo players get to play jesters, and the pair wins seed 578.
+        let solo = games(1, Some(2), 6..12);
+        let pairs = games(2, None, 576..582);
+        let results: Vec<_> = solo.iter().chain(&pairs).map(|(r, _)| r.clone()).collect();
+        let report = Report::new(&results);
+        assert_eq!(report.stats.len(), 2);
+        for (stats, games) in report.stats.iter().zip([&solo, &pairs]) {
+            let outcomes: Vec<_> = games.iter().map(|(_, o)| o).collect();
+            let victories = outcomes.iter().filter(|o| o.victory).count();
+            assert_eq!(stats.games, outcomes.len());
+            assert_eq!(stats.victories, victories);
+            assert_eq!(stats.stalled, 0);
+            assert_eq!(stats.win_rate, victories as f64 / outcomes.len() as f64);
+            assert_eq!(
+                stats.avg_enemies_defeated,
+                mean(&outcomes, |o| o.enemies_defeated)
+            );
+            assert_eq!(
+                stats.avg_jesters_played,
+                mean(&outcomes, |o| o.stats.jesters)
+            );
+            let killed: usize = stats.killers.iter().map(|(_, n)| n).sum();
+            assert_eq!(killed, outcomes.len() - victories);
+            assert!(stats.killers.windows(2).all(|w| w[0].1 >= w[1].1));
+        }
+        assert!(report.stats[0].avg_jesters_played > 0.0);
+        assert!(report.stats[1].win_rate > 0.0);
+    }
+
+    #[test]
+    fn stalled_games_count_as_neither_win_nor_loss() {
+        let rules = Ruleset {
+            min_players: 1,
+            max_players: 1,
+            seed: Some(0),
+            ..Ruleset::default()
+        };
+        let (result, _) = play_out(rules, 1, &bots, 3);
+        let report = Report::new(&[result]);
+        let stats = &report.stats[0];
+        assert_eq!((stats.games, stats.victories, stats.stalled), (1, 0, 1));
+        assert_eq!(stats.win_rate, 0.0);
+        assert!(stats.killers.is_empty());
+    }
+}
@@ -0,0 +1,229 @@
+use actor::{Action, UserId};
+use game::{
+    Card, GameState, Outcome, Phase, Policy, RegicideAction, Ruleset, SharedState, UserState, View,
+};
+use std::collections::HashMap;
+
+/// Builds the policy for one seat of one game, given a seed for that seat.
+pub trait PolicyFactory: Sync {
+    fn policy(&self, seat: usize, seed: u64) -> Box<dyn Policy>;
+}
+
+impl<F: Fn(usize, u64) -> Box<dyn Policy> + Sync> PolicyFactory for F {
+    fn policy(&self, seat: usize, seed: u64) -> Box<dyn Policy> {
+        self(seat, seed)
+    }
+}
+
+/// How a single simulated game went.
+#[derive(Clone, Debug)]
+pub struct GameResult {
+    pub players: u8,
+    /// `None` if the game hit the step limit or a policy stopped acting.
+    pub victory: Option<bool>,
+    pub enemies_defeated: u8,
+    pub jesters_played: u8,
+    /// The enemy in play when the party lost.
+    pub killer: Option<Card>,
+    pub steps: usize,
+}
+
+pub fn seat_id(seat: usize) -> UserId {
+    UserId::from(&format!("seat-{seat}")).expect("seat id fits")
+}
+
+/// Seats `players` and deals a game under `rules`.
+pub fn deal(rules: Ruleset, players: u8) -> (SharedState, HashMap<UserId, UserState>) {
+    let mut shared = RegicideAction::shared(&rules);
+    let mut user: HashMap<UserId, UserState> = (0..players as usize)
+        .map(|seat| (seat_id(seat), UserState::default()))
+        .collect();
+    RegicideAction::Init.update(&mut shared, &mut user, seat_id(0));
+    (shared, user)
+}
+
+/// Plays one game to the end through [`RegicideAction::update`].
+pub fn play(
+    rules: Ruleset,
+    players: u8,
+    factory: &impl PolicyFactory,
+    max_steps: usize,
+) -> GameResult {
+    play_out(rules, players, factory, max_steps).0
+}
+
+/// [`play`], along with the outcome the engine sums up for a finished game.
+pub(crate) fn play_out(
+    rules: Ruleset,
+    players: u8,
+    factory: &impl PolicyFactory,
+    max_steps: usize,
+) -> (GameResult, Option<Outcome>) {
+    let seed = rules.seed.unwrap_or_default();
+    let (mut shared, mut user) = deal(rules, players);
+    let mut policies: HashMap<UserId, Box<dyn Policy>> = (0..players as usize)
+        .map(|seat| {
+            let seat_seed = seed.wrapping_mul(31).wrapping_add(seat as u64);
+            (seat_id(seat), factory.policy(seat, seat_seed))
+        })
+        .collect();
+
+    let mut result = GameResult {
+        players,
+        victory: None,
+        enemies_defeated: 0,
+        jesters_played: 0,
+        killer: None,
+        steps: 0,
+    };
+    while result.steps < max_steps {
+        let GameState::Init { phase, deck, .. } = &shared.game else {
+            break;
+        };
+        let acting = match *phase {
+            Phase::Play(id) | Phase::Jester(id) | Phase::Defend(id) => id,
+            Phase::Victory | Phase::Defeat => {
+                result.victory = Some(*phase == Phase::Victory);
+                result.enemies_defeated = deck.defeated_count() as u8;
+                if *phase == Phase::Defeat {
+                    result.killer = deck.battling().copied();
+                }
+                break;
+            }
+        };
+        let Some(action) = View::new(&shared, &user, acting)
+            .and_then(|view| policies.get_mut(&acting)?.act(&view))
+        else {
+            break;
+        };
+        action.update(&mut shared, &mut user, acting);
+        if matches!(
+            shared.game,
+            GameState::Init {
+                phase: Phase::Jester(_),
+                ..
+            }
+        ) {
+            result.jesters_played += 1;
+        }
+        result.steps += 1;
+    }
+    let outcome = RegicideAction::summary(&shared, &user);
+    (result, outcome)
+}
+
+/// Runs `games` seeded games per player count, spread over `threads`.
+/// Game `i` is dealt from `seed + i`, so a run is reproducible.
+pub fn simulate(
+    rules: Ruleset,
+    player_counts: &[u8],
+    games: usize,
+    seed: u64,
+    threads: usize,
+    max_steps: usize,
+    factory: &impl PolicyFactory,
+) -> Vec<GameResult> {
+    let jobs: Vec<(u8, u64)> = player_counts
+        .iter()
+        .flat_map(|&players| (0..games as u64).map(move |i| (players, seed.wrapping_add(i))))
+        .collect();
+    let chunk = jobs.len().div_ceil(threads.max(1)).max(1);
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = jobs
+            .chunks(chunk)
+            .map(|jobs| {
+                scope.spawn(move || {
+                    jobs.iter()
+                        .map(|&(players, seed)| {
+                            let rules = Ruleset {
+                                min_players: players,
+                                max_players: players,
+                                seed: Some(seed),
+                                ..rules
+                            };
+                            play(rules, players, factory, max_steps)
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("simulation thread panicked"))
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game::{Bot, Difficulty, JsCard};
+    use rand::{SeedableRng, rngs::StdRng};
+
+    fn bots(_seat: usize, seed: u64) -> Box<dyn Policy> {
+        Box::new(Bot::with_rng(
+            Difficulty::Normal,
+            StdRng::seed_from_u64(seed),
+        ))
+    }
+
+    fn rules(players: u8, seed: u64) -> Ruleset {
+        Ruleset {
+            min_players: players,
+            max_players: players,
+            seed: Some(seed),
+            ..Ruleset::default()
+        }
+    }
+
+    fn card(card: Option<JsCard>) -> serde_json::Value {
+        serde_json::to_value(card).expect("cards serialize")
+    }
+
+    #[test]
+    fn results_agree_with_the_engine() {
+        // Solo players get to play jesters, and the pair wins seed 578.
+        let games = [(1, Some(2), 6..12), (2, None, 576..582), (4, None, 0..6)];
+        let (mut victories, mut jesters) = (0, 0);
+        for (players, jester_count, seeds) in games {
+            for seed in seeds {
+                let rules = Ruleset {
+                    jesters: jester_count,
+                    ..rules(players, seed)
+                };
+                let (result, outcome) = play_out(rules, players, &bots, 10_000);
+                let outcome = outcome.expect("bots finish every game");
+                assert_eq!(result.players, players);
+                assert_eq!(result.victory, Some(outcome.victory));
+                assert_eq!(result.enemies_defeated, outcome.enemies_defeated);
+                assert_eq!(result.jesters_played, outcome.stats.jesters);
+                assert_eq!(
+                    card(result.killer.map(JsCard::from)),
+                    card(outcome.defeated_by)
+                );
+                assert!(result.steps >= outcome.stats.turns as usize);
+                victories += outcome.victory as usize;
+                jesters += outcome.stats.jesters as usize;
+            }
+        }
+        assert!(victories > 0 && jesters > 0);
+    }
+
+    #[test]
+    fn a_seeded_game_plays_the_same_every_time() {
+        let a = play(rules(2, 3), 2, &bots, 10_000);
+        let b = play(rules(2, 3), 2, &bots, 10_000);
+        assert_eq!(
+            (a.victory, a.enemies_defeated, a.killer, a.steps),
+            (b.victory, b.enemies_defeated, b.killer, b.steps)
+        );
+    }
+
+    #[test]
+    fn the_step_limit_stalls_a_game() {
+        let (result, outcome) = play_out(rules(1, 0), 1, &bots, 3);
+        assert_eq!(result.victory, None);
+        assert_eq!(result.steps, 3);
+        assert!(outcome.is_none());
+    }
+}
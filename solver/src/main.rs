@@ -0,0 +1,100 @@
+use clap::Parser;
+use game::{RegicideAction, Ruleset};
+use solver::{Limits, Step, Verdict, solve};
+use std::process::ExitCode;
+
+/// Decides whether seeded Regicide deals can be won with perfect information.
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    /// Seed of the deal, as used by `sim` and `Ruleset::seed`.
+    #[arg(short, long, default_value_t = 0)]
+    seed: u64,
+    /// Solve this many consecutive seeds.
+    #[arg(short, long, default_value_t = 1)]
+    count: u64,
+    #[arg(short, long, default_value_t = 2)]
+    players: u8,
+    /// Override the number of jesters in the library.
+    #[arg(long)]
+    jesters: Option<u8>,
+    /// Positions to search per deal before giving up.
+    #[arg(long, default_value_t = 1_000_000)]
+    max_nodes: usize,
+    /// Only try the best N moves per position.
+    #[arg(long)]
+    beam: Option<usize>,
+    /// Print the winning line for each solved deal.
+    #[arg(short, long)]
+    verbose: bool,
+}
+
+fn describe(step: &Step) -> String {
+    let cards = step
+        .cards
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(" ");
+    match &step.action {
+        RegicideAction::Play { cards: c } if c.is_empty() => format!("{} yields", step.user_id),
+        RegicideAction::Play { .. } => format!("{} plays {cards}", step.user_id),
+        RegicideAction::Discard { .. } => format!("{} discards {cards}", step.user_id),
+        RegicideAction::Jester { player } => format!("{} passes to {player}", step.user_id),
+        action => format!("{} {action:?}", step.user_id),
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let rules = Ruleset {
+        min_players: cli.players,
+        max_players: cli.players,
+        jesters: cli.jesters,
+        seed: None,
+    };
+    if let Err(err) = rules.validate() {
+        eprintln!("error: {err}");
+        return ExitCode::FAILURE;
+    }
+    let limits = Limits {
+        max_nodes: cli.max_nodes,
+        beam: cli.beam,
+    };
+    let (mut won, mut lost, mut unknown) = (0, 0, 0);
+    for seed in cli.seed..cli.seed.saturating_add(cli.count) {
+        let rules = Ruleset {
+            seed: Some(seed),
+            ..rules
+        };
+        let (shared, user) = sim::deal(rules, cli.players);
+        let solution = solve(&shared, &user, limits);
+        match &solution.verdict {
+            Verdict::Win(steps) => {
+                won += 1;
+                println!(
+                    "seed {seed}: winnable in {} moves ({} positions)",
+                    steps.len(),
+                    solution.nodes
+                );
+                if cli.verbose {
+                    steps
+                        .iter()
+                        .for_each(|step| println!("  {}", describe(step)));
+                }
+            }
+            Verdict::Unwinnable => {
+                lost += 1;
+                println!("seed {seed}: unwinnable ({} positions)", solution.nodes);
+            }
+            Verdict::Unknown => {
+                unknown += 1;
+                println!("seed {seed}: unknown, search limit reached");
+            }
+        }
+    }
+    if cli.count > 1 {
+        println!("winnable {won}, unwinnable {lost}, unknown {unknown}");
+    }
+    ExitCode::SUCCESS
+}
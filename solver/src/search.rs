@@ -0,0 +1,387 @@
+use actor::{Action, UserId};
+use arrayvec::ArrayVec;
+use game::{Card, Combo, GameState, Phase, RegicideAction, SharedState, UserState, View, plays};
+use rand::rngs::StdRng;
+use std::collections::HashMap;
+
+/// Bounds on how hard [`solve`] tries.
+#[derive(Clone, Copy, Debug)]
+pub struct Limits {
+    /// Positions to expand before giving up.
+    pub max_nodes: usize,
+    /// Only try the best `beam` moves in each position. Searches with a beam
+    /// can find wins but never prove a deal unwinnable.
+    pub beam: Option<usize>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_nodes: 1_000_000,
+            beam: None,
+        }
+    }
+}
+
+/// One move of a solution, with the cards it spends.
+#[derive(Clone, Debug)]
+pub struct Step {
+    pub user_id: UserId,
+    pub action: RegicideAction,
+    pub cards: Vec<Card>,
+}
+
+#[derive(Clone, Debug)]
+pub enum Verdict {
+    Win(Vec<Step>),
+    /// Every line of play was searched and all of them lose.
+    Unwinnable,
+    /// The limits ran out first.
+    Unknown,
+}
+
+#[derive(Clone, Debug)]
+pub struct Solution {
+    pub verdict: Verdict,
+    pub nodes: usize,
+}
+
+/// Searches a dealt game with full knowledge of the library, the court and the
+/// shuffle, for a line of play that beats every enemy.
+pub fn solve(shared: &SharedState, user: &HashMap<UserId, UserState>, limits: Limits) -> Solution {
+    let mut search = Search {
+        limits,
+        nodes: 0,
+        lost: HashMap::new(),
+        shuffles: Vec::new(),
+        path: Vec::new(),
+    };
+    let verdict = match search.search(shared, user) {
+        Node::Win => Verdict::Win(search.path),
+        Node::Lost { proven: true } => Verdict::Unwinnable,
+        Node::Lost { proven: false } | Node::Cutoff => Verdict::Unknown,
+    };
+    Solution {
+        verdict,
+        nodes: search.nodes,
+    }
+}
+
+enum Node {
+    Win,
+    Lost { proven: bool },
+    Cutoff,
+}
+
+struct Search {
+    limits: Limits,
+    nodes: usize,
+    /// Transposition table of positions already known to lose, and whether
+    /// that is proven or only true within the beam. Keyed by the whole
+    /// position, so a hash collision can never pass one off as another.
+    lost: HashMap<Position, bool>,
+    /// Every state of the shuffle seen so far. It only moves when the deck
+    /// is reshuffled, so positions share a handful of them by index.
+    shuffles: Vec<StdRng>,
+    path: Vec<Step>,
+}
+
+impl Search {
+    fn search(&mut self, shared: &SharedState, user: &HashMap<UserId, UserState>) -> Node {
+        let GameState::Init { phase, .. } = &shared.game else {
+            return Node::Lost { proven: true };
+        };
+        match phase {
+            Phase::Victory => return Node::Win,
+            Phase::Defeat => return Node::Lost { proven: true },
+            _ => (),
+        }
+        let key = self.position(shared, user);
+        if let Some(&proven) = self.lost.get(&key) {
+            return Node::Lost { proven };
+        }
+        self.nodes += 1;
+        if self.nodes > self.limits.max_nodes {
+            return Node::Cutoff;
+        }
+
+        let mut moves = moves(shared, user);
+        let mut proven = true;
+        if let Some(beam) = self.limits.beam
+            && moves.len() > beam
+        {
+            moves.truncate(beam);
+            proven = false;
+        }
+        for step in moves {
+            let (mut shared, mut user) = (shared.clone(), user.clone());
+            step.action
+                .clone()
+                .update(&mut shared, &mut user, step.user_id);
+            // Moves the rules refuse leave the position unchanged.
+            if self.position(&shared, &user) == key {
+                continue;
+            }
+            self.path.push(step);
+            match self.search(&shared, &user) {
+                Node::Win => return Node::Win,
+                Node::Cutoff => return Node::Cutoff,
+                Node::Lost { proven: child } => proven &= child,
+            }
+            self.path.pop();
+        }
+        self.lost.insert(key, proven);
+        Node::Lost { proven }
+    }
+}
+
+/// Everything that decides how the rest of the game plays out: the shuffle
+/// by its index in [`Search::shuffles`] and the rest encoded whole. Hands are
+/// sorted since card order within a hand only changes the indices actions
+/// refer to.
+#[derive(PartialEq, Eq, Hash)]
+struct Position {
+    shuffle: usize,
+    cards: Box<[u8]>,
+}
+
+impl Search {
+    fn position(&mut self, shared: &SharedState, user: &HashMap<UserId, UserState>) -> Position {
+        let GameState::Init {
+            phase,
+            deck,
+            turn_order,
+            damage,
+            yields,
+            ..
+        } = &shared.game
+        else {
+            unreachable!("only dealt games are searched");
+        };
+        let hands: Vec<_> = turn_order
+            .iter()
+            .map(|id| {
+                let mut hand = match user.get(id) {
+                    Some(UserState::Init { hand }) => hand.clone(),
+                    _ => ArrayVec::new(),
+                };
+                hand.sort_by_key(|card| *card as u8);
+                (id, hand)
+            })
+            .collect();
+        // Decks serialize without their shuffle, which is compared apart.
+        let cards = postcard::to_stdvec(&(phase, deck, damage, yields, hands))
+            .expect("positions always serialize")
+            .into_boxed_slice();
+        let shuffle = match self.shuffles.iter().rposition(|rng| rng == deck.rng()) {
+            Some(shuffle) => shuffle,
+            None => {
+                self.shuffles.push(deck.rng().clone());
+                self.shuffles.len() - 1
+            }
+        };
+        Position { shuffle, cards }
+    }
+}
+
+/// Every discard that survives `need` damage, cheapest first. Spending more
+/// than the attack needs can still matter, since hearts later shuffle the
+/// discard pile back into the library.
+fn discards(hand: &[Card], need: u8) -> Vec<ArrayVec<u8, 8>> {
+    let strength = |mask: u16| -> u16 {
+        (0..hand.len())
+            .filter(|i| mask & (1 << i) != 0)
+            .map(|i| hand[i].strength() as u16)
+            .sum()
+    };
+    let mut masks: Vec<u16> = (0u16..1 << hand.len())
+        .filter(|&mask| strength(mask) >= need as u16)
+        .collect();
+    masks.sort_by_key(|&mask| (strength(mask), mask.count_ones()));
+    masks
+        .into_iter()
+        .map(|mask| {
+            (0..hand.len() as u8)
+                .filter(|i| mask & (1 << i) != 0)
+                .collect()
+        })
+        .collect()
+}
+
+/// Candidate moves for whoever acts next, most promising first.
+fn moves(shared: &SharedState, user: &HashMap<UserId, UserState>) -> Vec<Step> {
+    let GameState::Init {
+        phase, turn_order, ..
+    } = &shared.game
+    else {
+        return Vec::new();
+    };
+    let (Phase::Play(id) | Phase::Jester(id) | Phase::Defend(id)) = *phase else {
+        return Vec::new();
+    };
+    let Some(view) = View::new(shared, user, id) else {
+        return Vec::new();
+    };
+    let step = |action: RegicideAction, cards: &[u8]| Step {
+        user_id: id,
+        action,
+        cards: cards.iter().map(|i| view.hand[*i as usize]).collect(),
+    };
+    match *phase {
+        Phase::Play(_) => {
+            let health = view.health();
+            let mut plays = plays(view.hand);
+            // Kills that overshoot least, then the hardest hits.
+            plays.sort_by_key(|(cards, combo)| {
+                let strength = combo.strength();
+                let jester = matches!(combo, Combo::Jester);
+                if strength >= health && !jester {
+                    (0, strength - health, cards.len())
+                } else {
+                    (1, u8::MAX - strength, cards.len())
+                }
+            });
+            let mut moves: Vec<_> = plays
+                .into_iter()
+                .map(|(cards, _)| {
+                    step(
+                        RegicideAction::Play {
+                            cards: cards.clone(),
+                        },
+                        &cards,
+                    )
+                })
+                .collect();
+            moves.push(step(
+                RegicideAction::Play {
+                    cards: ArrayVec::new(),
+                },
+                &[],
+            ));
+            moves
+        }
+        Phase::Defend(_) => discards(view.hand, view.attack())
+            .into_iter()
+            .map(|cards| {
+                step(
+                    RegicideAction::Discard {
+                        cards: cards.clone(),
+                    },
+                    &cards,
+                )
+            })
+            .collect(),
+        Phase::Jester(_) => turn_order
+            .iter()
+            .map(|player| step(RegicideAction::Jester { player: *player }, &[]))
+            .collect(),
+        Phase::Victory | Phase::Defeat => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game::Ruleset;
+
+    fn deal(seed: u64) -> (SharedState, HashMap<UserId, UserState>) {
+        let rules = Ruleset {
+            min_players: 1,
+            seed: Some(seed),
+            ..Ruleset::default()
+        };
+        sim::deal(rules, 1)
+    }
+
+    /// Enough for the small positions below, so a regression fails fast
+    /// instead of searching a whole deal.
+    fn small() -> Limits {
+        Limits {
+            max_nodes: 1_000,
+            beam: None,
+        }
+    }
+
+    fn search() -> Search {
+        Search {
+            limits: Limits::default(),
+            nodes: 0,
+            lost: HashMap::new(),
+            shuffles: Vec::new(),
+            path: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn discards_include_sets_that_spend_more_than_needed() {
+        let hand = [Card::SpadeTwo, Card::SpadeThree, Card::SpadeFour];
+        let discards = discards(&hand, 5);
+        assert_eq!(discards.first().map(|d| d.as_slice()), Some(&[0, 1][..]));
+        assert!(discards.iter().any(|d| d.as_slice() == [0, 1, 2]));
+        assert!(!discards.iter().any(|d| d.as_slice() == [2]));
+        assert_eq!(super::discards(&hand, 0).len(), 8);
+    }
+
+    #[test]
+    fn hand_order_does_not_change_the_position() {
+        let (shared, mut user) = deal(1);
+        let mut search = search();
+        let before = search.position(&shared, &user);
+        user.values_mut().for_each(|u| {
+            if let UserState::Init { hand } = u {
+                hand.reverse();
+            }
+        });
+        assert!(search.position(&shared, &user) == before);
+        assert_eq!(search.shuffles.len(), 1);
+    }
+
+    #[test]
+    fn different_deals_are_different_positions() {
+        let mut search = search();
+        let (a, a_user) = deal(1);
+        let (b, b_user) = deal(2);
+        assert!(search.position(&a, &a_user) != search.position(&b, &b_user));
+    }
+
+    #[test]
+    fn a_found_win_replays_to_victory() {
+        let (mut shared, mut user) = deal(52);
+        let Verdict::Win(steps) = solve(&shared, &user, small()).verdict else {
+            panic!("seed 52 has a known win");
+        };
+        steps
+            .into_iter()
+            .for_each(|step| step.action.update(&mut shared, &mut user, step.user_id));
+        assert!(matches!(
+            shared.game,
+            GameState::Init {
+                phase: Phase::Victory,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn an_exhausted_search_proves_the_loss() {
+        let (mut shared, mut user) = deal(9);
+        // Greedy play loses this deal in nine moves; five before the end,
+        // every line left is lost.
+        for _ in 0..4 {
+            let step = moves(&shared, &user).remove(0);
+            step.action.update(&mut shared, &mut user, step.user_id);
+        }
+        assert!(matches!(
+            solve(&shared, &user, small()).verdict,
+            Verdict::Unwinnable
+        ));
+        let beam = Limits {
+            beam: Some(1),
+            ..small()
+        };
+        assert!(!matches!(
+            solve(&shared, &user, beam).verdict,
+            Verdict::Unwinnable
+        ));
+    }
+}
@@ -1,4 +1,100 @@
+use backend::Config;
+use clap::Parser;
+use std::{net::SocketAddr, path::PathBuf, process::ExitCode};
+
+/// Regicide game server.
+///
+/// Settings are read from the config file, then overridden by environment
+/// variables, then by flags.
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    /// TOML config file, see `config.example.toml`.
+    #[arg(short, long, env = "REGICIDE_CONFIG")]
+    config: Option<PathBuf>,
+    #[arg(long, env = "REGICIDE_BIND_ADDR")]
+    bind_addr: Option<SocketAddr>,
+    #[arg(long, env = "REGICIDE_STATIC_DIR")]
+    static_dir: Option<PathBuf>,
+    #[arg(long, env = "REGICIDE_DATABASE_PATH")]
+    database_path: Option<PathBuf>,
+    #[arg(long, env = "REGICIDE_SNAPSHOT_DIR")]
+    snapshot_dir: Option<PathBuf>,
+    #[arg(long, env = "REGICIDE_JOIN_BOUND")]
+    join_bound: Option<usize>,
+    #[arg(long, env = "REGICIDE_MSG_BOUND")]
+    msg_bound: Option<usize>,
+    #[arg(long, env = "REGICIDE_TICK_MS")]
+    tick_ms: Option<u64>,
+    #[arg(long, env = "REGICIDE_RECV_BOUND")]
+    recv_bound: Option<usize>,
+    #[arg(long, env = "REGICIDE_RECONNECT_AFTER_SECS")]
+    reconnect_after_secs: Option<u64>,
+    #[arg(long, env = "REGICIDE_PING_INTERVAL_SECS")]
+    ping_interval_secs: Option<u64>,
+    #[arg(long, env = "REGICIDE_PING_TIMEOUT_SECS")]
+    ping_timeout_secs: Option<u64>,
+    /// Seats in new lobbies.
+    #[arg(long, env = "REGICIDE_MAX_PLAYERS")]
+    max_players: Option<u8>,
+    /// Players needed to start a game.
+    #[arg(long, env = "REGICIDE_MIN_PLAYERS")]
+    min_players: Option<u8>,
+    /// Oldest client protocol revision to accept.
+    #[arg(long, env = "REGICIDE_MIN_PROTOCOL_VERSION")]
+    min_protocol_version: Option<u16>,
+    /// Bearer token enabling the admin API.
+    #[arg(long, env = "REGICIDE_ADMIN_TOKEN", hide_env_values = true)]
+    admin_token: Option<String>,
+}
+
+impl Cli {
+    fn config(self) -> Result<Config, backend::ConfigError> {
+        let mut config = match &self.config {
+            Some(path) => Config::from_file(path)?,
+            None => Config::default(),
+        };
+        macro_rules! set {
+            ($($flag:ident => $($field:ident).+),* $(,)?) => {
+                $(if let Some(value) = self.$flag {
+                    config.$($field).+ = value;
+                })*
+            };
+        }
+        set! {
+            bind_addr => bind_addr,
+            static_dir => static_dir,
+            database_path => database_path,
+            snapshot_dir => snapshot_dir,
+            join_bound => join_bound,
+            msg_bound => msg_bound,
+            tick_ms => actor.tick_ms,
+            recv_bound => actor.recv_bound,
+            reconnect_after_secs => reconnect_after_secs,
+            ping_interval_secs => ping_interval_secs,
+            ping_timeout_secs => ping_timeout_secs,
+            max_players => rules.max_players,
+            min_players => rules.min_players,
+            min_protocol_version => min_protocol_version,
+        }
+        if let Some(token) = self.admin_token {
+            config.admin_token = Some(token);
+        }
+        config.validate()?;
+        Ok(config)
+    }
+}
+
 #[tokio::main]
-async fn main() {
-    backend::serve().await
+async fn main() -> ExitCode {
+    match Cli::parse().config() {
+        Ok(config) => {
+            backend::serve(config).await;
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
 }
@@ -0,0 +1,11 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Database error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("Serialization error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Invalid stored id: {0}")]
+    InvalidId(String),
+}
@@ -0,0 +1,10 @@
+pub mod error;
+pub mod migrations;
+pub mod record;
+pub mod sqlite;
+pub mod store;
+
+pub use error::*;
+pub use record::*;
+pub use sqlite::*;
+pub use store::*;
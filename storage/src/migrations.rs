@@ -0,0 +1,57 @@
+use crate::Error;
+use rusqlite::Connection;
+use tracing::info;
+
+/// Schema migrations, applied in order. Never edit an entry once it has shipped;
+/// append a new one instead. The index of the last applied migration is kept in
+/// `PRAGMA user_version`.
+const MIGRATIONS: &[&str] = &[
+    // 1: accounts, finished games and replays
+    "CREATE TABLE accounts (
+        user_id TEXT PRIMARY KEY NOT NULL,
+        created_at INTEGER NOT NULL,
+        last_seen INTEGER NOT NULL
+    );
+    CREATE TABLE games (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        actor_id TEXT NOT NULL,
+        finished_at INTEGER NOT NULL,
+        summary TEXT NOT NULL
+    );
+    CREATE TABLE game_players (
+        game_id INTEGER NOT NULL REFERENCES games(id) ON DELETE CASCADE,
+        user_id TEXT NOT NULL,
+        PRIMARY KEY (game_id, user_id)
+    );
+    CREATE INDEX game_players_user ON game_players(user_id);
+    CREATE TABLE replays (
+        actor_id TEXT NOT NULL,
+        seq INTEGER NOT NULL,
+        user_id TEXT NOT NULL,
+        at INTEGER NOT NULL,
+        action TEXT NOT NULL,
+        PRIMARY KEY (actor_id, seq)
+    );",
+    // 2: the seed each game at a table was dealt from, and which of those
+    // games a finished one was
+    "CREATE TABLE deals (
+        actor_id TEXT NOT NULL,
+        game INTEGER NOT NULL,
+        seq INTEGER NOT NULL,
+        seed INTEGER NOT NULL,
+        PRIMARY KEY (actor_id, game)
+    );
+    ALTER TABLE games ADD COLUMN game INTEGER NOT NULL DEFAULT 0;",
+];
+
+pub fn migrate(conn: &mut Connection) -> Result<(), Error> {
+    let version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(migration)?;
+        tx.pragma_update(None, "user_version", i + 1)?;
+        tx.commit()?;
+        info!(version = i + 1, "Applied database migration");
+    }
+    Ok(())
+}
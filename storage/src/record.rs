@@ -0,0 +1,196 @@
+use crate::{Deal, GameRecord, ReplayStep, Storage, now};
+use actor::{Action, ActorId, Record};
+use kanal::Receiver;
+use std::collections::HashMap;
+use tracing::{debug, error, info, warn};
+
+/// Replay steps are written out once this many are buffered for an actor.
+const REPLAY_FLUSH: usize = 64;
+
+struct Replay {
+    next_seq: u32,
+    steps: Vec<ReplayStep>,
+}
+
+impl Replay {
+    /// Picks up after the steps already stored, so a restarted server
+    /// appends to a table's replay instead of colliding with it.
+    fn resume<S: Storage>(storage: &S, actor_id: ActorId) -> Self {
+        let next_seq = storage.next_replay_seq(actor_id).unwrap_or_else(|err| {
+            error!(%actor_id, error = %err, "Failed to read replay length");
+            0
+        });
+        Self {
+            next_seq,
+            steps: Vec::new(),
+        }
+    }
+}
+
+/// Persists records from the actor system until the channel closes,
+/// then flushes any buffered replays. Warns once more than `backlog`
+/// records are waiting, and again each time the backlog clears and refills.
+pub fn record_loop<A: Action, S: Storage>(
+    recv: Receiver<Record<A>>,
+    mut storage: S,
+    backlog: usize,
+) {
+    info!("Record loop started");
+    let mut replays: HashMap<ActorId, Replay> = HashMap::new();
+    let mut behind = false;
+    while let Ok(record) = recv.recv() {
+        let waiting = recv.len();
+        if waiting > backlog && !behind {
+            warn!(waiting, "Storage is falling behind the actor system");
+        }
+        behind = waiting > backlog;
+        match record {
+            Record::Join { user_id, actor_id } => {
+                debug!(%user_id, %actor_id, "Recording account");
+                if let Err(err) = storage.touch_account(user_id) {
+                    error!(%user_id, error = %err, "Failed to record account");
+                }
+            }
+            Record::Action {
+                actor_id,
+                user_id,
+                action,
+            } => {
+                let action = match serde_json::to_string(&action) {
+                    Ok(action) => action,
+                    Err(err) => {
+                        error!(%actor_id, %user_id, error = %err, "Failed to serialize action");
+                        continue;
+                    }
+                };
+                let replay = replays
+                    .entry(actor_id)
+                    .or_insert_with(|| Replay::resume(&storage, actor_id));
+                replay.steps.push(ReplayStep {
+                    seq: replay.next_seq,
+                    user_id,
+                    at: now(),
+                    action,
+                });
+                replay.next_seq += 1;
+                if replay.steps.len() >= REPLAY_FLUSH {
+                    flush(&mut storage, actor_id, replay);
+                }
+            }
+            Record::Dealt {
+                actor_id,
+                game,
+                seed,
+            } => {
+                let replay = replays
+                    .entry(actor_id)
+                    .or_insert_with(|| Replay::resume(&storage, actor_id));
+                let deal = Deal {
+                    game,
+                    seq: replay.next_seq,
+                    seed,
+                };
+                match storage.save_deal(actor_id, &deal) {
+                    Ok(()) => debug!(%actor_id, game, seq = deal.seq, "Recorded deal"),
+                    Err(err) => error!(%actor_id, error = %err, "Failed to record deal"),
+                }
+            }
+            Record::Finished {
+                actor_id,
+                game,
+                players,
+                summary,
+            } => {
+                if let Some(replay) = replays.get_mut(&actor_id) {
+                    flush(&mut storage, actor_id, replay);
+                }
+                let summary = match serde_json::to_string(&summary) {
+                    Ok(summary) => summary,
+                    Err(err) => {
+                        error!(%actor_id, error = %err, "Failed to serialize game summary");
+                        continue;
+                    }
+                };
+                let game = GameRecord {
+                    actor_id,
+                    game,
+                    players,
+                    finished_at: now(),
+                    summary,
+                };
+                match storage.save_game(&game) {
+                    Ok(()) => info!(%actor_id, "Recorded finished game"),
+                    Err(err) => error!(%actor_id, error = %err, "Failed to record finished game"),
+                }
+            }
+            Record::Closed { actor_id } => {
+                if let Some(mut replay) = replays.remove(&actor_id) {
+                    flush(&mut storage, actor_id, &mut replay);
+                }
+            }
+        }
+    }
+    replays
+        .iter_mut()
+        .for_each(|(actor_id, replay)| flush(&mut storage, *actor_id, replay));
+    info!("Record loop stopped");
+}
+
+fn flush<S: Storage>(storage: &mut S, actor_id: ActorId, replay: &mut Replay) {
+    if replay.steps.is_empty() {
+        return;
+    }
+    // Steps stay buffered after a failed write and go out with the next flush.
+    match storage.append_replay(actor_id, &replay.steps) {
+        Ok(()) => {
+            debug!(%actor_id, steps = replay.steps.len(), "Flushed replay");
+            replay.steps.clear();
+        }
+        Err(err) => error!(%actor_id, error = %err, "Failed to flush replay"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SqliteStorage;
+    use actor::UserId;
+    use uuid::Uuid;
+
+    fn step(seq: u32) -> ReplayStep {
+        ReplayStep {
+            seq,
+            user_id: UserId::from("ann").unwrap(),
+            at: now(),
+            action: "\"Init\"".into(),
+        }
+    }
+
+    #[test]
+    fn a_resumed_replay_appends_after_stored_steps() {
+        let mut storage = SqliteStorage::in_memory().unwrap();
+        let actor_id = Uuid::now_v7();
+        storage
+            .append_replay(actor_id, &[step(0), step(1)])
+            .unwrap();
+        let mut replay = Replay::resume(&storage, actor_id);
+        assert_eq!(replay.next_seq, 2);
+        replay.steps.push(step(replay.next_seq));
+        flush(&mut storage, actor_id, &mut replay);
+        assert!(replay.steps.is_empty());
+        assert_eq!(storage.replay(actor_id).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn a_failed_flush_keeps_the_steps() {
+        let mut storage = SqliteStorage::in_memory().unwrap();
+        let actor_id = Uuid::now_v7();
+        storage.append_replay(actor_id, &[step(0)]).unwrap();
+        let mut replay = Replay {
+            next_seq: 0,
+            steps: vec![step(0)],
+        };
+        flush(&mut storage, actor_id, &mut replay);
+        assert_eq!(replay.steps.len(), 1);
+    }
+}
@@ -0,0 +1,276 @@
+use crate::{Account, Deal, Error, GameRecord, ReplayStep, Storage, migrations::migrate, now};
+use actor::{ActorId, UserId};
+use rusqlite::{Connection, OptionalExtension, Row, params};
+use std::path::Path;
+use tracing::info;
+
+pub struct SqliteStorage {
+    conn: Connection,
+}
+
+impl SqliteStorage {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let conn = Connection::open(path)?;
+        info!(path = %path.display(), "Opened SQLite database");
+        Self::new(conn)
+    }
+    pub fn in_memory() -> Result<Self, Error> {
+        Self::new(Connection::open_in_memory()?)
+    }
+    fn new(mut conn: Connection) -> Result<Self, Error> {
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "foreign_keys", true)?;
+        migrate(&mut conn)?;
+        Ok(Self { conn })
+    }
+}
+
+fn parse_user_id(s: String) -> Result<UserId, Error> {
+    UserId::from(&s).map_err(|_| Error::InvalidId(s.clone()))
+}
+
+fn parse_actor_id(s: String) -> Result<ActorId, Error> {
+    ActorId::parse_str(&s).map_err(|_| Error::InvalidId(s.clone()))
+}
+
+fn step_row(row: &Row) -> rusqlite::Result<(u32, String, i64, String)> {
+    Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+}
+
+fn steps(
+    rows: impl Iterator<Item = rusqlite::Result<(u32, String, i64, String)>>,
+) -> Result<Vec<ReplayStep>, Error> {
+    rows.map(|row| {
+        let (seq, id, at, action) = row?;
+        Ok(ReplayStep {
+            seq,
+            user_id: parse_user_id(id)?,
+            at,
+            action,
+        })
+    })
+    .collect()
+}
+
+impl Storage for SqliteStorage {
+    fn touch_account(&mut self, user_id: UserId) -> Result<(), Error> {
+        let now = now();
+        self.conn.execute(
+            "INSERT INTO accounts (user_id, created_at, last_seen) VALUES (?1, ?2, ?2)
+             ON CONFLICT(user_id) DO UPDATE SET last_seen = excluded.last_seen",
+            params![user_id.as_str(), now],
+        )?;
+        Ok(())
+    }
+    fn account(&self, user_id: UserId) -> Result<Option<Account>, Error> {
+        let row = self
+            .conn
+            .query_row(
+                "SELECT created_at, last_seen FROM accounts WHERE user_id = ?1",
+                params![user_id.as_str()],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        Ok(row.map(|(created_at, last_seen)| Account {
+            user_id,
+            created_at,
+            last_seen,
+        }))
+    }
+    fn save_game(&mut self, game: &GameRecord) -> Result<(), Error> {
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT INTO games (actor_id, game, finished_at, summary) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                game.actor_id.to_string(),
+                game.game,
+                game.finished_at,
+                game.summary
+            ],
+        )?;
+        let game_id = tx.last_insert_rowid();
+        {
+            let mut insert =
+                tx.prepare("INSERT INTO game_players (game_id, user_id) VALUES (?1, ?2)")?;
+            for player in &game.players {
+                insert.execute(params![game_id, player.as_str()])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+    fn games(&self, user_id: UserId, limit: usize) -> Result<Vec<GameRecord>, Error> {
+        let mut games = self.conn.prepare(
+            "SELECT g.id, g.actor_id, g.game, g.finished_at, g.summary FROM games g
+             JOIN game_players p ON p.game_id = g.id
+             WHERE p.user_id = ?1 ORDER BY g.finished_at DESC LIMIT ?2",
+        )?;
+        let mut players = self
+            .conn
+            .prepare("SELECT user_id FROM game_players WHERE game_id = ?1")?;
+        let rows = games
+            .query_map(params![user_id.as_str(), limit as i64], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, u32>(2)?,
+                    row.get::<_, i64>(3)?,
+                    row.get::<_, String>(4)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows.into_iter()
+            .map(|(game_id, aid, game, finished_at, summary)| {
+                let players = players
+                    .query_map(params![game_id], |row| row.get::<_, String>(0))?
+                    .map(|id| parse_user_id(id?))
+                    .collect::<Result<_, _>>()?;
+                Ok(GameRecord {
+                    actor_id: parse_actor_id(aid)?,
+                    game,
+                    players,
+                    finished_at,
+                    summary,
+                })
+            })
+            .collect()
+    }
+    fn append_replay(&mut self, actor_id: ActorId, steps: &[ReplayStep]) -> Result<(), Error> {
+        let tx = self.conn.transaction()?;
+        {
+            let mut insert = tx.prepare(
+                "INSERT INTO replays (actor_id, seq, user_id, at, action) VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            let actor_id = actor_id.to_string();
+            for step in steps {
+                insert.execute(params![
+                    actor_id,
+                    step.seq,
+                    step.user_id.as_str(),
+                    step.at,
+                    step.action
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+    fn replay(&self, actor_id: ActorId) -> Result<Vec<ReplayStep>, Error> {
+        let mut select = self.conn.prepare(
+            "SELECT seq, user_id, at, action FROM replays WHERE actor_id = ?1 ORDER BY seq",
+        )?;
+        steps(select.query_map(params![actor_id.to_string()], step_row)?)
+    }
+    fn game_replay(&self, actor_id: ActorId, game: u32) -> Result<Vec<ReplayStep>, Error> {
+        let mut select = self.conn.prepare(
+            "SELECT r.seq, r.user_id, r.at, r.action FROM replays r
+             JOIN deals d ON d.actor_id = r.actor_id AND d.game = ?2
+             WHERE r.actor_id = ?1 AND r.seq >= d.seq AND NOT EXISTS (
+                 SELECT 1 FROM deals n
+                 WHERE n.actor_id = ?1 AND n.game > ?2 AND n.seq <= r.seq
+             )
+             ORDER BY r.seq",
+        )?;
+        steps(select.query_map(params![actor_id.to_string(), game], step_row)?)
+    }
+    fn next_replay_seq(&self, actor_id: ActorId) -> Result<u32, Error> {
+        Ok(self.conn.query_row(
+            "SELECT COALESCE(MAX(seq) + 1, 0) FROM replays WHERE actor_id = ?1",
+            params![actor_id.to_string()],
+            |row| row.get(0),
+        )?)
+    }
+    fn save_deal(&mut self, actor_id: ActorId, deal: &Deal) -> Result<(), Error> {
+        // SQLite integers are signed; the seed round-trips through i64 bit for bit.
+        self.conn.execute(
+            "INSERT OR REPLACE INTO deals (actor_id, game, seq, seed) VALUES (?1, ?2, ?3, ?4)",
+            params![actor_id.to_string(), deal.game, deal.seq, deal.seed as i64],
+        )?;
+        Ok(())
+    }
+    fn deals(&self, actor_id: ActorId) -> Result<Vec<Deal>, Error> {
+        let mut select = self
+            .conn
+            .prepare("SELECT game, seq, seed FROM deals WHERE actor_id = ?1 ORDER BY game")?;
+        let deals = select
+            .query_map(params![actor_id.to_string()], |row| {
+                Ok(Deal {
+                    game: row.get(0)?,
+                    seq: row.get(1)?,
+                    seed: row.get::<_, i64>(2)? as u64,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+        Ok(deals)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn step(seq: u32) -> ReplayStep {
+        ReplayStep {
+            seq,
+            user_id: UserId::from("ann").unwrap(),
+            at: now(),
+            action: "\"Init\"".into(),
+        }
+    }
+
+    #[test]
+    fn replay_seq_continues_after_stored_steps() {
+        let mut storage = SqliteStorage::in_memory().unwrap();
+        let actor_id = Uuid::now_v7();
+        assert_eq!(storage.next_replay_seq(actor_id).unwrap(), 0);
+        storage
+            .append_replay(actor_id, &[step(0), step(1), step(2)])
+            .unwrap();
+        assert_eq!(storage.next_replay_seq(actor_id).unwrap(), 3);
+        assert_eq!(storage.next_replay_seq(Uuid::now_v7()).unwrap(), 0);
+    }
+
+    #[test]
+    fn deals_keep_the_full_seed() {
+        let mut storage = SqliteStorage::in_memory().unwrap();
+        let actor_id = Uuid::now_v7();
+        let deals = [
+            Deal {
+                game: 0,
+                seq: 0,
+                seed: 7,
+            },
+            Deal {
+                game: 1,
+                seq: 40,
+                seed: u64::MAX,
+            },
+        ];
+        deals
+            .iter()
+            .for_each(|deal| storage.save_deal(actor_id, deal).unwrap());
+        assert_eq!(storage.deals(actor_id).unwrap(), deals);
+    }
+
+    #[test]
+    fn a_game_replay_stops_at_the_next_deal() {
+        let mut storage = SqliteStorage::in_memory().unwrap();
+        let actor_id = Uuid::now_v7();
+        storage
+            .append_replay(actor_id, &(0..6).map(step).collect::<Vec<_>>())
+            .unwrap();
+        [(0, 1), (1, 4)].into_iter().for_each(|(game, seq)| {
+            let deal = Deal { game, seq, seed: 7 };
+            storage.save_deal(actor_id, &deal).unwrap();
+        });
+        let seqs = |game| {
+            let steps = storage.game_replay(actor_id, game).unwrap();
+            steps.iter().map(|step| step.seq).collect::<Vec<_>>()
+        };
+        assert_eq!(seqs(0), [1, 2, 3]);
+        assert_eq!(seqs(1), [4, 5]);
+        assert!(seqs(2).is_empty());
+    }
+}
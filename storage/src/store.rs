@@ -0,0 +1,70 @@
+use crate::Error;
+use actor::{ActorId, UserId};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Persistent store for accounts, finished games and replays.
+pub trait Storage: Send + 'static {
+    /// Creates the account on first sight and bumps `last_seen` afterwards.
+    fn touch_account(&mut self, user_id: UserId) -> Result<(), Error>;
+    fn account(&self, user_id: UserId) -> Result<Option<Account>, Error>;
+    fn save_game(&mut self, game: &GameRecord) -> Result<(), Error>;
+    /// Most recent finished games the user took part in, newest first.
+    fn games(&self, user_id: UserId, limit: usize) -> Result<Vec<GameRecord>, Error>;
+    fn append_replay(&mut self, actor_id: ActorId, steps: &[ReplayStep]) -> Result<(), Error>;
+    fn replay(&self, actor_id: ActorId) -> Result<Vec<ReplayStep>, Error>;
+    /// The steps played in one game at the actor's table, from its deal up to
+    /// the next one.
+    fn game_replay(&self, actor_id: ActorId, game: u32) -> Result<Vec<ReplayStep>, Error>;
+    /// The seq the actor's next replay step takes, one past the last stored.
+    fn next_replay_seq(&self, actor_id: ActorId) -> Result<u32, Error>;
+    fn save_deal(&mut self, actor_id: ActorId, deal: &Deal) -> Result<(), Error>;
+    /// Every game dealt at the actor's table, oldest first.
+    fn deals(&self, actor_id: ActorId) -> Result<Vec<Deal>, Error>;
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Account {
+    pub user_id: UserId,
+    pub created_at: i64,
+    pub last_seen: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GameRecord {
+    pub actor_id: ActorId,
+    /// Which game at the table this was, as numbered by its [`Deal`].
+    pub game: u32,
+    pub players: Vec<UserId>,
+    pub finished_at: i64,
+    /// JSON encoded `Action::Summary`.
+    pub summary: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReplayStep {
+    pub seq: u32,
+    pub user_id: UserId,
+    pub at: i64,
+    /// JSON encoded action.
+    pub action: String,
+}
+
+/// A game starting at one table. Replaying the steps from `seq` up to the
+/// next deal, with the deck shuffled from `seed`, plays the game out again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deal {
+    /// Games dealt at the table before this one.
+    pub game: u32,
+    /// The first replay step played in the game.
+    pub seq: u32,
+    pub seed: u64,
+}
+
+/// Milliseconds since the unix epoch.
+pub fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}